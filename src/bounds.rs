@@ -0,0 +1,185 @@
+use cosmwasm_std::{Decimal256, Deps, DepsMut, Env, Response, SignedDecimal256, StdResult, Storage};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::currency_pair::CurrencyPair;
+use crate::error::ContractError;
+use crate::math::{deviation_bps, price_to_decimal};
+use crate::query::GetPriceResponse;
+use crate::state::Contract;
+
+/// Circuit breaker of a pair: prices outside the bounds are rejected instead of served.
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct PairBounds {
+    /// in whole quote units
+    pub min_price: Option<SignedDecimal256>,
+    pub max_price: Option<SignedDecimal256>,
+    /// max move from `reference_price`
+    pub max_deviation_bps: Option<u64>,
+    /// set by the admin rather than taken from recorded prices, which callers could walk away
+    pub reference_price: Option<SignedDecimal256>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct PairBoundsResponse {
+    /// none when the pair is unbounded
+    pub bounds: Option<PairBounds>,
+}
+
+impl<'a> Contract<'a> {
+    // authorized by the caller, shared by execute and sudo
+    pub(crate) fn set_pair_bounds(
+        &self,
+        deps: DepsMut,
+        _env: Env,
+        pair: CurrencyPair,
+        bounds: Option<PairBounds>,
+    ) -> Result<Response, ContractError> {
+        let pair_id = pair.to_string();
+        match &bounds {
+            Some(bounds) => {
+                if let (Some(min_price), Some(max_price)) = (bounds.min_price, bounds.max_price) {
+                    if min_price > max_price {
+                        return Err(ContractError::InvalidPairBounds { reason: "min_price exceeds max_price".to_string() });
+                    }
+                }
+                if bounds.max_deviation_bps == Some(0) {
+                    return Err(ContractError::InvalidPairBounds { reason: "max_deviation_bps must be positive".to_string() });
+                }
+                if bounds.max_deviation_bps.is_some() && bounds.reference_price.filter(|price| *price > SignedDecimal256::zero()).is_none() {
+                    return Err(ContractError::InvalidPairBounds { reason: "max_deviation_bps requires a positive reference_price".to_string() });
+                }
+                self.pair_bounds.save(deps.storage, &pair_id, bounds)?;
+            }
+            None => self.pair_bounds.remove(deps.storage, &pair_id),
+        }
+
+        Ok(Response::new()
+            .add_attribute("action", "set_pair_bounds")
+            .add_attribute("pair", pair_id)
+            .add_attribute("bounded", bounds.is_some().to_string()))
+    }
+
+    pub(crate) fn get_pair_bounds(&self, deps: Deps, _env: Env, pair: CurrencyPair) -> StdResult<PairBoundsResponse> {
+        Ok(PairBoundsResponse { bounds: self.pair_bounds.may_load(deps.storage, &pair.to_string())? })
+    }
+
+    // trips the circuit breaker of the pair on prices outside its bounds, executes skipping the
+    // pair add `price_rejected_event` of the error to their response
+    pub(crate) fn assert_within_bounds(&self, storage: &dyn Storage, pair_id: &str, price: &GetPriceResponse) -> Result<(), ContractError> {
+        let bounds = match self.pair_bounds.may_load(storage, pair_id)? {
+            Some(bounds) => bounds,
+            None => return Ok(()),
+        };
+        let value = price_to_decimal(price.price.price, price.decimals)?;
+        let out_of_bounds = |reason: String| ContractError::PriceOutOfBounds { pair: pair_id.to_string(), reason };
+
+        if let Some(min_price) = bounds.min_price.filter(|min_price| value < *min_price) {
+            return Err(out_of_bounds(format!("{} is below min_price {}", value, min_price)));
+        }
+        if let Some(max_price) = bounds.max_price.filter(|max_price| value > *max_price) {
+            return Err(out_of_bounds(format!("{} is above max_price {}", value, max_price)));
+        }
+        if let (Some(max_deviation_bps), Some(reference)) = (bounds.max_deviation_bps, bounds.reference_price) {
+            let deviation = deviation_bps(value, reference)?;
+            if deviation > Decimal256::from_ratio(max_deviation_bps, 1u64) {
+                return Err(out_of_bounds(format!(
+                    "{} deviates {} bps from reference_price {}, max is {}", value, deviation, reference, max_deviation_bps,
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+    use crate::msgs::{ExecuteMsg, QueryMsg, SudoMsg};
+    use crate::testing::{error_of, Suite, ADMIN, KEEPER, USER};
+
+    fn decimal(value: &str) -> Option<SignedDecimal256> {
+        Some(SignedDecimal256::from_str(value).unwrap())
+    }
+
+    fn bounds(min_price: &str, max_price: &str) -> PairBounds {
+        PairBounds { min_price: decimal(min_price), max_price: decimal(max_price), max_deviation_bps: None, reference_price: None }
+    }
+
+    fn deviation(max_deviation_bps: u64, reference_price: &str) -> PairBounds {
+        PairBounds { min_price: None, max_price: None, max_deviation_bps: Some(max_deviation_bps), reference_price: decimal(reference_price) }
+    }
+
+    fn set_bounds(suite: &mut Suite, bounds: Option<PairBounds>) -> anyhow::Result<cw_multi_test::AppResponse> {
+        suite.execute(ADMIN, &ExecuteMsg::SetPairBounds { pair: "BTC/USD".parse().unwrap(), bounds }, &[])
+    }
+
+    fn get_price(suite: &Suite) -> StdResult<GetPriceResponse> {
+        suite.query(&QueryMsg::GetPrice { pair: "BTC/USD".parse().unwrap() })
+    }
+
+    #[test]
+    fn prices_outside_bounds_are_rejected() {
+        let mut suite = Suite::new();
+        suite.set_price("BTC/USD", 6_000_000, 2);
+        set_bounds(&mut suite, Some(bounds("10000", "100000"))).unwrap();
+        get_price(&suite).unwrap();
+
+        suite.set_price("BTC/USD", 100, 2);
+        let err = get_price(&suite).unwrap_err().to_string();
+        assert!(err.contains("Price of BTC/USD out of bounds: 1 is below min_price 10000"), "{}", err);
+        suite.set_price("BTC/USD", 20_000_000, 2);
+        assert!(get_price(&suite).unwrap_err().to_string().contains("above max_price"));
+        let batch = suite.query::<crate::query::GetPricesResponse>(&QueryMsg::GetPrices { pair_ids: vec!["BTC/USD".parse().unwrap()] });
+        assert!(batch.unwrap_err().to_string().contains("out of bounds"));
+
+        // tripped prices are not recorded
+        let err = suite.execute(KEEPER, &ExecuteMsg::QuoteAndRecord { pairs: vec!["BTC/USD".parse().unwrap()] }, &[]).unwrap_err();
+        assert!(error_of(err).contains("out of bounds"));
+
+        set_bounds(&mut suite, None).unwrap();
+        get_price(&suite).unwrap();
+    }
+
+    #[test]
+    fn deviation_from_the_reference_price() {
+        let mut suite = Suite::new();
+        suite.set_price("BTC/USD", 11_000, 0);
+        set_bounds(&mut suite, Some(deviation(1_000, "10000"))).unwrap();
+        get_price(&suite).unwrap();
+        suite.set_price("BTC/USD", 8_999, 0);
+        assert!(get_price(&suite).unwrap_err().to_string().contains("deviates"));
+
+        // recorded prices do not move the reference
+        for price in [10_900, 11_800, 12_700] {
+            suite.set_price("BTC/USD", price, 0);
+            let _ = suite.execute(KEEPER, &ExecuteMsg::QuoteAndRecord { pairs: vec!["BTC/USD".parse().unwrap()] }, &[]);
+            suite.advance(60);
+        }
+        assert!(get_price(&suite).unwrap_err().to_string().contains("from reference_price 10000"));
+    }
+
+    #[test]
+    fn set_pair_bounds_validation() {
+        let mut suite = Suite::new();
+        let err = set_bounds(&mut suite, Some(bounds("2", "1"))).unwrap_err();
+        assert_eq!(error_of(err), "Invalid pair bounds: min_price exceeds max_price");
+        let err = set_bounds(&mut suite, Some(deviation(0, "1"))).unwrap_err();
+        assert_eq!(error_of(err), "Invalid pair bounds: max_deviation_bps must be positive");
+        let err = set_bounds(&mut suite, Some(PairBounds { reference_price: None, ..deviation(5, "1") })).unwrap_err();
+        assert_eq!(error_of(err), "Invalid pair bounds: max_deviation_bps requires a positive reference_price");
+
+        let msg = ExecuteMsg::SetPairBounds { pair: "BTC/USD".parse().unwrap(), bounds: None };
+        assert_eq!(error_of(suite.execute(USER, &msg, &[]).unwrap_err()), "Unauthorized");
+
+        suite.sudo(&SudoMsg::SetPairBounds { pair: "btc/usd".parse().unwrap(), bounds: Some(deviation(5, "2")) }).unwrap();
+        let res: PairBoundsResponse = suite.query(&QueryMsg::GetPairBounds { pair: "BTC/USD".parse().unwrap() }).unwrap();
+        assert_eq!(res.bounds, Some(deviation(5, "2")));
+
+        suite.sudo(&SudoMsg::SetPairBounds { pair: "BTC/USD".parse().unwrap(), bounds: None }).unwrap();
+        let res: PairBoundsResponse = suite.query(&QueryMsg::GetPairBounds { pair: "BTC/USD".parse().unwrap() }).unwrap();
+        assert_eq!(res.bounds, None);
+    }
+}
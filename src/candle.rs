@@ -0,0 +1,283 @@
+use cosmwasm_std::{Deps, DepsMut, Env, Order, Response, SignedDecimal256, StdResult, Storage, Timestamp};
+use cw_storage_plus::Bound;
+use serde::{Deserialize, Serialize};
+
+use crate::currency_pair::CurrencyPair;
+use crate::error::ContractError;
+use crate::math::price_to_decimal;
+use crate::query::GetPriceResponse;
+use crate::state::Contract;
+
+/// 1m, 1h and 1d
+pub const DEFAULT_CANDLE_INTERVALS: [u64; 3] = [60, 3_600, 86_400];
+/// every interval adds a write per recorded price
+const MAX_CANDLE_INTERVALS: usize = 8;
+/// 30 days
+const MAX_CANDLE_INTERVAL: u64 = 30 * 86_400;
+/// candles kept per pair and interval, older ones are pruned as new ones open
+pub const CANDLE_RETENTION: u64 = 1_000;
+const PRUNE_LIMIT: usize = 10;
+
+const DEFAULT_LIMIT: u32 = 10;
+const MAX_LIMIT: u32 = 100;
+
+/// Open, high, low and close of the prices recorded within an interval.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct Candle {
+    pub open_time: Timestamp,
+    /// in whole quote units
+    pub open: SignedDecimal256,
+    pub high: SignedDecimal256,
+    pub low: SignedDecimal256,
+    pub close: SignedDecimal256,
+    /// recorded prices in the candle
+    pub observations: u32,
+}
+
+impl<'a> Contract<'a> {
+    // authorized by the caller, shared by execute and sudo
+    pub(crate) fn set_candle_intervals(
+        &self,
+        deps: DepsMut,
+        _env: Env,
+        mut intervals: Vec<u64>,
+    ) -> Result<Response, ContractError> {
+        intervals.sort_unstable();
+        intervals.dedup();
+        let invalid = |interval: &u64| *interval == 0 || *interval > MAX_CANDLE_INTERVAL;
+        if intervals.iter().any(invalid) || intervals.len() > MAX_CANDLE_INTERVALS {
+            return Err(ContractError::InvalidCandleIntervals { max: MAX_CANDLE_INTERVALS, max_interval: MAX_CANDLE_INTERVAL });
+        }
+        let removed: Vec<u64> = self.candle_intervals(deps.storage)?
+            .into_iter()
+            .filter(|interval| !intervals.contains(interval))
+            .collect();
+        self.candle_intervals.save(deps.storage, &intervals)?;
+        // candles of removed intervals would no longer be updated nor pruned
+        for interval in removed.iter() {
+            self.remove_candles(deps.storage, *interval)?;
+        }
+
+        // no intervals disables candles
+        let intervals: Vec<_> = intervals.iter().map(ToString::to_string).collect();
+        let removed: Vec<_> = removed.iter().map(ToString::to_string).collect();
+        Ok(Response::new()
+            .add_attribute("action", "set_candle_intervals")
+            .add_attributes((!intervals.is_empty()).then(|| ("intervals", intervals.join(","))))
+            .add_attributes((!removed.is_empty()).then(|| ("removed_intervals", removed.join(",")))))
+    }
+
+    // every candle of `interval`, pairs with candles are the recorded ones so they all have a cumulative price
+    fn remove_candles(&self, storage: &mut dyn Storage, interval: u64) -> StdResult<()> {
+        let pair_ids = self.cumulative_prices
+            .keys(storage, None, None, Order::Ascending)
+            .collect::<StdResult<Vec<_>>>()?;
+        for pair_id in pair_ids {
+            let open_times = self.candles
+                .prefix((&pair_id, interval))
+                .keys(storage, None, None, Order::Ascending)
+                .collect::<StdResult<Vec<_>>>()?;
+            for open_time in open_times {
+                self.candles.remove(storage, (&pair_id, interval, open_time));
+            }
+        }
+        Ok(())
+    }
+
+    pub(crate) fn candle_intervals(&self, storage: &dyn Storage) -> StdResult<Vec<u64>> {
+        Ok(self.candle_intervals.may_load(storage)?.unwrap_or_else(|| DEFAULT_CANDLE_INTERVALS.to_vec()))
+    }
+
+    pub(crate) fn update_candles(&self, storage: &mut dyn Storage, env: &Env, pair_id: &str, price: &GetPriceResponse) -> StdResult<()> {
+        let price = price_to_decimal(price.price.price, price.decimals)?;
+        let now = env.block.time.seconds();
+        for interval in self.candle_intervals(storage)? {
+            let open_time = now - now % interval;
+            let candle = match self.candles.may_load(storage, (pair_id, interval, open_time))? {
+                Some(candle) => Candle {
+                    high: candle.high.max(price),
+                    low: candle.low.min(price),
+                    close: price,
+                    observations: candle.observations + 1,
+                    ..candle
+                },
+                None => {
+                    self.prune_candles(storage, pair_id, interval, open_time)?;
+                    Candle {
+                        open_time: Timestamp::from_seconds(open_time),
+                        open: price,
+                        high: price,
+                        low: price,
+                        close: price,
+                        observations: 1,
+                    }
+                }
+            };
+            self.candles.save(storage, (pair_id, interval, open_time), &candle)?;
+        }
+        Ok(())
+    }
+
+    // keeps the latest `CANDLE_RETENTION` candles, the one opening at `open_time` included
+    fn prune_candles(&self, storage: &mut dyn Storage, pair_id: &str, interval: u64, open_time: u64) -> StdResult<()> {
+        let cutoff = open_time.saturating_sub((CANDLE_RETENTION - 1).saturating_mul(interval));
+        let expired = self.candles
+            .prefix((pair_id, interval))
+            .keys(storage, None, Some(Bound::exclusive(cutoff)), Order::Ascending)
+            .take(PRUNE_LIMIT)
+            .collect::<StdResult<Vec<_>>>()?;
+        for open_time in expired {
+            self.candles.remove(storage, (pair_id, interval, open_time));
+        }
+        Ok(())
+    }
+
+    pub(crate) fn get_candle_intervals(&self, deps: Deps, _env: Env) -> StdResult<Vec<u64>> {
+        self.candle_intervals(deps.storage)
+    }
+
+    pub(crate) fn get_candles(
+        &self,
+        deps: Deps,
+        _env: Env,
+        pair: CurrencyPair,
+        interval: u64,
+        start_after: Option<Timestamp>,
+        limit: Option<u32>,
+    ) -> Result<CandlesResponse, ContractError> {
+        let pair_id = pair.to_string();
+        self.assert_pairs_not_paused(deps.storage, std::slice::from_ref(&pair_id))?;
+        let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+        let candles = self.candles
+            .prefix((&pair_id, interval))
+            .range(deps.storage, start_after.map(|time| Bound::exclusive(time.seconds())), None, Order::Ascending)
+            .take(limit)
+            .map(|item| item.map(|(_, candle)| candle))
+            .collect::<StdResult<Vec<_>>>()?;
+
+        Ok(CandlesResponse { pair_id, interval, candles })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct CandlesResponse {
+    pub pair_id: String,
+    pub interval: u64,
+    /// oldest first
+    pub candles: Vec<Candle>,
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+    use crate::msgs::{ExecuteMsg, QueryMsg, SudoMsg};
+    use crate::testing::{attribute, error_of, Suite, ADMIN, KEEPER, USER};
+
+    fn decimal(value: &str) -> SignedDecimal256 {
+        SignedDecimal256::from_str(value).unwrap()
+    }
+
+    // records `price` and moves on by `seconds`
+    fn record(suite: &mut Suite, price: i128, seconds: u64) {
+        suite.set_price("BTC/USD", price, 0);
+        suite.execute(KEEPER, &ExecuteMsg::QuoteAndRecord { pairs: vec!["BTC/USD".parse().unwrap()] }, &[]).unwrap();
+        suite.advance(seconds);
+    }
+
+    fn candles(suite: &Suite, interval: u64, start_after: Option<Timestamp>, limit: Option<u32>) -> Vec<Candle> {
+        let msg = QueryMsg::GetCandles { pair: "BTC/USD".parse().unwrap(), interval, start_after, limit };
+        suite.query::<CandlesResponse>(&msg).unwrap().candles
+    }
+
+    fn set_intervals(suite: &mut Suite, intervals: Vec<u64>) -> anyhow::Result<cw_multi_test::AppResponse> {
+        suite.execute(ADMIN, &ExecuteMsg::SetCandleIntervals { intervals }, &[])
+    }
+
+    #[test]
+    fn aggregates_recorded_prices() {
+        let mut suite = Suite::new();
+        // the default mock block is 39 seconds into a minute
+        record(&mut suite, 100, 5);
+        record(&mut suite, 120, 5);
+        record(&mut suite, 90, 5);
+        record(&mut suite, 110, 50);
+        record(&mut suite, 130, 0);
+
+        let minutes = candles(&suite, 60, None, None);
+        assert_eq!(minutes.len(), 2);
+        let first = &minutes[0];
+        assert_eq!(first.open_time.seconds() % 60, 0);
+        assert_eq!((first.open, first.high, first.low, first.close), (decimal("100"), decimal("120"), decimal("90"), decimal("110")));
+        assert_eq!(first.observations, 4);
+        assert_eq!((minutes[1].open, minutes[1].observations), (decimal("130"), 1));
+
+        let hours = candles(&suite, 3_600, None, None);
+        assert_eq!((hours.len(), hours[0].close, hours[0].observations), (1, decimal("130"), 5));
+        assert_eq!(candles(&suite, 60, Some(first.open_time), None), minutes[1..]);
+        assert_eq!(candles(&suite, 60, None, Some(1)), minutes[..1]);
+        assert!(candles(&suite, 120, None, None).is_empty());
+    }
+
+    #[test]
+    fn old_candles_are_pruned() {
+        let mut suite = Suite::new();
+        set_intervals(&mut suite, vec![1]).unwrap();
+        for _ in 0..CANDLE_RETENTION + 5 {
+            record(&mut suite, 100, 1);
+        }
+        let mut kept = 0;
+        let mut start_after = None;
+        loop {
+            let page = candles(&suite, 1, start_after, Some(100));
+            match page.last() {
+                Some(candle) => start_after = Some(candle.open_time),
+                None => break,
+            }
+            kept += page.len() as u64;
+        }
+        assert_eq!(kept, CANDLE_RETENTION);
+    }
+
+    #[test]
+    fn set_candle_intervals_validation() {
+        let mut suite = Suite::new();
+        let invalid = "Candle intervals must be positive and at most 2592000 seconds, at most 8 of them";
+        assert_eq!(error_of(set_intervals(&mut suite, vec![0]).unwrap_err()), invalid);
+        assert_eq!(error_of(set_intervals(&mut suite, vec![MAX_CANDLE_INTERVAL + 1]).unwrap_err()), invalid);
+        assert_eq!(error_of(set_intervals(&mut suite, (1..=9).collect()).unwrap_err()), invalid);
+        let msg = ExecuteMsg::SetCandleIntervals { intervals: vec![60] };
+        assert_eq!(error_of(suite.execute(USER, &msg, &[]).unwrap_err()), "Unauthorized");
+
+        let res = set_intervals(&mut suite, vec![3_600, 60, 60, MAX_CANDLE_INTERVAL]).unwrap();
+        assert_eq!(attribute(&res, "intervals"), "60,3600,2592000");
+        let intervals: Vec<u64> = suite.query(&QueryMsg::GetCandleIntervals {}).unwrap();
+        assert_eq!(intervals, vec![60, 3_600, MAX_CANDLE_INTERVAL]);
+
+        // no intervals disables candles
+        let res = suite.sudo(&SudoMsg::SetCandleIntervals { intervals: vec![] }).unwrap();
+        assert_eq!(attribute(&res, "intervals"), "");
+        record(&mut suite, 100, 0);
+        assert!(candles(&suite, 60, None, None).is_empty());
+    }
+
+    #[test]
+    fn removed_intervals_lose_their_candles() {
+        let mut suite = Suite::new();
+        record(&mut suite, 100, 60);
+        record(&mut suite, 110, 0);
+        assert_eq!(candles(&suite, 60, None, None).len(), 2);
+
+        let res = set_intervals(&mut suite, vec![3_600]).unwrap();
+        assert_eq!(attribute(&res, "removed_intervals"), "60,86400");
+        assert_eq!(candles(&suite, 3_600, None, None).len(), 1);
+
+        // adding an interval back starts from scratch
+        set_intervals(&mut suite, vec![60, 3_600]).unwrap();
+        assert!(candles(&suite, 60, None, None).is_empty());
+        assert!(candles(&suite, 86_400, None, None).is_empty());
+        record(&mut suite, 120, 0);
+        assert_eq!(candles(&suite, 60, None, None).len(), 1);
+    }
+}
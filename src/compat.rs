@@ -0,0 +1,290 @@
+use cosmwasm_std::{Deps, Env, Int256, Int64, StdError, StdResult, Uint256, Uint64};
+
+use crate::currency_pair::CurrencyPair;
+use crate::error::ContractError;
+use crate::math::{decimal_to_price, pow10, unsigned_price};
+use crate::query::GetPriceResponse;
+use crate::state::Contract;
+
+// half-life of the ema reported by `price_feed` unless registered otherwise
+pub const DEFAULT_PYTH_EMA_HALF_LIFE: u64 = 60;
+
+// chainlink AggregatorV3Interface compatible queries, one feed per currency pair
+impl<'a> Contract<'a> {
+    pub(crate) fn latest_round_data(&self, deps: Deps, env: Env, pair: CurrencyPair) -> Result<RoundDataResponse, ContractError> {
+        let res = self.get_price(deps, env, pair)?;
+        Ok(RoundDataResponse::from(res))
+    }
+
+    // slinky keeps only the latest price, so only the current round can be answered
+    pub(crate) fn get_round_data(&self, deps: Deps, env: Env, pair: CurrencyPair, round_id: u64) -> Result<RoundDataResponse, ContractError> {
+        let feed = pair.to_string();
+        let res = self.get_price(deps, env, pair)?;
+        if res.nonce != round_id {
+            return Err(StdError::not_found(format!("round {} of {} (latest round is {})", round_id, feed, res.nonce)).into());
+        }
+        Ok(RoundDataResponse::from(res))
+    }
+
+    pub(crate) fn decimals(&self, deps: Deps, env: Env, pair: CurrencyPair) -> Result<u64, ContractError> {
+        Ok(self.get_price(deps, env, pair)?.decimals)
+    }
+
+    pub(crate) fn description(&self, _deps: Deps, _env: Env, pair: CurrencyPair) -> StdResult<String> {
+        Ok(format!("{} / {}", pair.base(), pair.quote()))
+    }
+}
+
+// band std_reference compatible queries
+impl<'a> Contract<'a> {
+    pub(crate) fn get_reference_data(&self, deps: Deps, env: Env, base_symbol: String, quote_symbol: String) -> Result<ReferenceData, ContractError> {
+        let res = self.get_price(deps, env, CurrencyPair::new(&base_symbol, &quote_symbol)?)?;
+        Ok(ReferenceData::try_from(res)?)
+    }
+
+    pub(crate) fn get_reference_data_bulk(&self, deps: Deps, env: Env, base_symbols: Vec<String>, quote_symbols: Vec<String>) -> Result<Vec<ReferenceData>, ContractError> {
+        if base_symbols.len() != quote_symbols.len() {
+            return Err(StdError::generic_err("base_symbols and quote_symbols must have the same length").into());
+        }
+        if base_symbols.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let pair_ids = base_symbols
+            .iter()
+            .zip(quote_symbols.iter())
+            .map(|(base, quote)| Ok(CurrencyPair::new(base, quote)?.to_string()))
+            .collect::<Result<_, ContractError>>()?;
+        Ok(self.get_prices(deps, env, pair_ids)?
+            .prices
+            .into_iter()
+            .map(ReferenceData::try_from)
+            .collect::<StdResult<_>>()?)
+    }
+}
+
+// pyth cosmwasm compatible queries, feeds are mapped to currency pairs by the admin
+impl<'a> Contract<'a> {
+    pub(crate) fn price_feed(&self, deps: Deps, env: Env, id: String) -> Result<PriceFeedResponse, ContractError> {
+        let feed = self.pyth_feeds.load(deps.storage, &id)?;
+        let pair = CurrencyPair::new(&feed.base, &feed.quote)?;
+        let res = self.get_price(deps, env.clone(), pair.clone())?;
+        // slinky has no ema, it is computed from the prices recorded by this contract
+        let ema = self.get_ema(deps, env, pair, feed.ema_half_life)?;
+        let price = PythPrice::try_from(res)?;
+        let ema_price = PythPrice {
+            price: Int64::try_from(decimal_to_price(ema.price, price.expo.unsigned_abs() as u64)?)
+                .map_err(|_| StdError::generic_err("ema price overflows i64"))?,
+            ..price.clone()
+        };
+
+        Ok(PriceFeedResponse {
+            price_feed: PriceFeed {
+                id,
+                price,
+                ema_price,
+            },
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct RoundDataResponse {
+    /// slinky nonce of the pair
+    pub round_id: u64,
+    pub answer: Int256,
+    /// unix seconds
+    pub started_at: u64,
+    /// unix seconds of the slinky block timestamp
+    pub updated_at: u64,
+    pub answered_in_round: u64,
+}
+
+impl From<GetPriceResponse> for RoundDataResponse {
+    fn from(res: GetPriceResponse) -> Self {
+        let updated_at = res.price.block_timestamp.seconds();
+        RoundDataResponse {
+            round_id: res.nonce,
+            answer: res.price.price,
+            started_at: updated_at,
+            updated_at,
+            answered_in_round: res.nonce,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct ReferenceData {
+    /// base/quote rate multiplied by 1e18
+    pub rate: Uint256,
+    pub last_updated_base: u64,
+    pub last_updated_quote: u64,
+}
+
+impl TryFrom<GetPriceResponse> for ReferenceData {
+    type Error = StdError;
+
+    fn try_from(res: GetPriceResponse) -> StdResult<Self> {
+        // band rates are unsigned
+        let price = unsigned_price(res.price.price)?;
+        let rate = if res.decimals <= 18 {
+            price.checked_mul(Uint256::try_from(pow10(18 - res.decimals)?)?)?
+        } else {
+            price.checked_div(Uint256::try_from(pow10(res.decimals - 18)?)?)?
+        };
+        let last_updated = res.price.block_timestamp.seconds();
+
+        Ok(ReferenceData {
+            rate,
+            last_updated_base: last_updated,
+            last_updated_quote: last_updated,
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct PriceFeedResponse {
+    pub price_feed: PriceFeed,
+}
+
+#[derive(Clone, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct PriceFeed {
+    pub id: String,
+    pub price: PythPrice,
+    pub ema_price: PythPrice,
+}
+
+#[derive(Clone, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct PythPrice {
+    pub price: Int64,
+    /// slinky reports no confidence interval, always zero
+    pub conf: Uint64,
+    pub expo: i32,
+    pub publish_time: i64,
+}
+
+impl TryFrom<GetPriceResponse> for PythPrice {
+    type Error = StdError;
+
+    fn try_from(res: GetPriceResponse) -> StdResult<Self> {
+        let price = Int64::try_from(res.price.price)
+            .map_err(|_| StdError::generic_err("price overflows i64"))?;
+        let expo = i32::try_from(res.decimals)
+            .map_err(|_| StdError::generic_err("decimals out of range"))?;
+
+        Ok(PythPrice {
+            price,
+            conf: Uint64::zero(),
+            expo: -expo,
+            publish_time: res.price.block_timestamp.seconds() as i64,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::msgs::{ExecuteMsg, QueryMsg};
+    use crate::testing::{Suite, ADMIN, KEEPER, USER};
+
+    fn btc_usd() -> CurrencyPair {
+        CurrencyPair::new("BTC", "USD").unwrap()
+    }
+
+    #[test]
+    fn latest_round_data() {
+        let mut suite = Suite::new();
+        suite.set_price("BTC/USD", 6_500_000_000_000, 8);
+        suite.app.update_block(|block| block.time = block.time.plus_seconds(10));
+        suite.set_price("BTC/USD", -1, 8);
+
+        let res: RoundDataResponse = suite.query(&QueryMsg::LatestRoundData { pair: btc_usd() }).unwrap();
+        let updated_at = suite.app.block_info().time.seconds();
+        assert_eq!(res, RoundDataResponse { round_id: 2, answer: Int256::from(-1i64), started_at: updated_at, updated_at, answered_in_round: 2 });
+
+        let decimals: u64 = suite.query(&QueryMsg::Decimals { pair: btc_usd() }).unwrap();
+        assert_eq!(decimals, 8);
+        let description: String = suite.query(&QueryMsg::Description { pair: btc_usd() }).unwrap();
+        assert_eq!(description, "BTC / USD");
+    }
+
+    #[test]
+    fn only_the_latest_round_is_available() {
+        let suite = Suite::new();
+        suite.set_price("BTC/USD", 100, 0);
+        suite.set_price("BTC/USD", 101, 0);
+
+        let res: RoundDataResponse = suite.query(&QueryMsg::GetRoundData { pair: btc_usd(), round_id: 2 }).unwrap();
+        assert_eq!(res.answer, Int256::from(101u64));
+        let res: StdResult<RoundDataResponse> = suite.query(&QueryMsg::GetRoundData { pair: btc_usd(), round_id: 1 });
+        assert!(res.unwrap_err().to_string().contains("latest round is 2"));
+    }
+
+    #[test]
+    fn band_reference_data() {
+        let suite = Suite::new();
+        suite.set_price("BTC/USD", 6_500_000_000_000, 8);
+        suite.set_price("ETH/USD", 3_000_000_000_000_000_000_000_000, 21);
+        let time = suite.app.block_info().time.seconds();
+
+        let query = QueryMsg::GetReferenceData { base_symbol: "btc".to_string(), quote_symbol: "USD".to_string() };
+        let res: ReferenceData = suite.query(&query).unwrap();
+        assert_eq!(res, ReferenceData { rate: Uint256::from(65_000u64) * Uint256::from(10u64).pow(18), last_updated_base: time, last_updated_quote: time });
+
+        let query = QueryMsg::GetReferenceDataBulk {
+            base_symbols: vec!["BTC".to_string(), "ETH".to_string()],
+            quote_symbols: vec!["USD".to_string(), "USD".to_string()],
+        };
+        let res: Vec<ReferenceData> = suite.query(&query).unwrap();
+        assert_eq!(res[1].rate, Uint256::from(3_000u64) * Uint256::from(10u64).pow(18));
+
+        let query = QueryMsg::GetReferenceDataBulk { base_symbols: vec!["BTC".to_string()], quote_symbols: vec![] };
+        assert!(suite.query::<Vec<ReferenceData>>(&query).is_err());
+
+        // band rates are unsigned
+        suite.set_price("BTC/USD", -1, 8);
+        let query = QueryMsg::GetReferenceData { base_symbol: "BTC".to_string(), quote_symbol: "USD".to_string() };
+        assert!(suite.query::<ReferenceData>(&query).is_err());
+    }
+
+    #[test]
+    fn pyth_price_feed() {
+        let mut suite = Suite::new();
+        suite.set_price("BTC/USD", 6_500_000_000_000, 8);
+        let id = "e62df6c8b4a85fe1a67db44dc12de5db330f7ac66b72dc658afedf0f4a415b43".to_string();
+        let query = QueryMsg::PriceFeed { id: id.clone() };
+        assert!(suite.query::<PriceFeedResponse>(&query).is_err());
+
+        let msg = ExecuteMsg::RegisterPythFeed { id: id.clone(), base: "BTC".to_string(), quote: "USD".to_string(), ema_half_life: Some(3600) };
+        assert!(suite.execute(USER, &msg, &[]).is_err());
+        suite.execute(ADMIN, &msg, &[]).unwrap();
+        // the ema needs a recorded price
+        assert!(suite.query::<PriceFeedResponse>(&query).unwrap_err().to_string().contains("BTC/USD"));
+
+        let record = ExecuteMsg::QuoteAndRecord { pairs: vec![btc_usd()] };
+        suite.execute(KEEPER, &record, &[]).unwrap();
+        suite.advance(3600);
+        suite.set_price("BTC/USD", 7_000_000_000_000, 8);
+        suite.execute(KEEPER, &record, &[]).unwrap();
+        suite.advance(3600);
+
+        // one half-life after the move the ema is halfway, 65000 + (70000 - 65000) / 2
+        let res: PriceFeedResponse = suite.query(&query).unwrap();
+        let price = PythPrice {
+            price: Int64::new(7_000_000_000_000),
+            conf: Uint64::zero(),
+            expo: -8,
+            publish_time: suite.app.block_info().time.seconds() as i64 - 3600,
+        };
+        assert_eq!(res.price_feed.price, price);
+        let ema_price = res.price_feed.ema_price;
+        assert!((ema_price.price.i64() - 6_750_000_000_000).abs() <= 1, "{:?}", ema_price);
+        assert_eq!(PythPrice { price: price.price, ..ema_price }, price);
+
+        let msg = ExecuteMsg::RegisterPythFeed { id: id.clone(), base: "BTC".to_string(), quote: "USD".to_string(), ema_half_life: Some(0) };
+        assert!(suite.execute(ADMIN, &msg, &[]).is_err());
+
+        suite.execute(ADMIN, &ExecuteMsg::RemovePythFeed { id }, &[]).unwrap();
+        assert!(suite.query::<PriceFeedResponse>(&query).is_err());
+    }
+}
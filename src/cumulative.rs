@@ -0,0 +1,134 @@
+use cosmwasm_std::{Deps, Env, SignedDecimal256, StdResult, Storage, Timestamp};
+use serde::{Deserialize, Serialize};
+
+use crate::currency_pair::CurrencyPair;
+use crate::error::ContractError;
+use crate::math::price_to_decimal;
+use crate::query::GetPriceResponse;
+use crate::state::Contract;
+
+/// Uniswap v2 style time weighted accumulator of a pair's recorded prices.
+///
+/// A consumer checkpoints `cumulative` and `block_time` of two `get_cumulative` reads,
+/// the twap over that window is `(cumulative_2 - cumulative_1) / (block_time_2 - block_time_1)`.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct CumulativePrice {
+    /// sum of price * seconds, in whole quote units
+    pub cumulative: SignedDecimal256,
+    /// latest recorded price, accrues until the next record
+    pub price: SignedDecimal256,
+    /// block time of the latest record
+    pub last_updated: Timestamp,
+}
+
+impl<'a> Contract<'a> {
+    pub(crate) fn accumulate(&self, storage: &mut dyn Storage, env: &Env, pair_id: &str, price: &GetPriceResponse) -> StdResult<()> {
+        let price = price_to_decimal(price.price.price, price.decimals)?;
+        let cumulative = match self.cumulative_prices.may_load(storage, pair_id)? {
+            Some(last) => last.accrued_until(env.block.time)?,
+            None => SignedDecimal256::zero(),
+        };
+        self.cumulative_prices.save(storage, pair_id, &CumulativePrice { cumulative, price, last_updated: env.block.time })
+    }
+
+    pub(crate) fn get_cumulative(&self, deps: Deps, env: Env, pair: CurrencyPair) -> Result<CumulativeResponse, ContractError> {
+        let pair_id = pair.to_string();
+        self.assert_pairs_not_paused(deps.storage, std::slice::from_ref(&pair_id))?;
+        let last = self.cumulative_prices.load(deps.storage, &pair_id)?;
+
+        Ok(CumulativeResponse {
+            cumulative: last.accrued_until(env.block.time)?,
+            block_time: env.block.time,
+            price: last.price,
+            last_updated: last.last_updated,
+            pair_id,
+        })
+    }
+}
+
+impl CumulativePrice {
+    // accumulator value at `time`, as if the latest price held until then
+    pub fn accrued_until(&self, time: Timestamp) -> StdResult<SignedDecimal256> {
+        let elapsed = time.seconds().saturating_sub(self.last_updated.seconds());
+        Ok(self.cumulative.checked_add(self.price.checked_mul(SignedDecimal256::from_ratio(elapsed, 1u64))?)?)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct CumulativeResponse {
+    pub pair_id: String,
+    /// accrued up to `block_time`
+    pub cumulative: SignedDecimal256,
+    pub block_time: Timestamp,
+    pub price: SignedDecimal256,
+    pub last_updated: Timestamp,
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+    use crate::msgs::{ExecuteMsg, PauseTarget, QueryMsg};
+    use crate::testing::{Suite, ADMIN, KEEPER};
+
+    fn decimal(value: &str) -> SignedDecimal256 {
+        SignedDecimal256::from_str(value).unwrap()
+    }
+
+    fn record(suite: &mut Suite) {
+        suite.execute(KEEPER, &ExecuteMsg::QuoteAndRecord { pairs: vec!["BTC/USD".parse().unwrap()] }, &[]).unwrap();
+    }
+
+    fn cumulative(suite: &Suite) -> StdResult<CumulativeResponse> {
+        suite.query(&QueryMsg::GetCumulative { pair: "BTC/USD".parse().unwrap() })
+    }
+
+    #[test]
+    fn accrues_each_price_until_the_next_record() {
+        let mut suite = Suite::new();
+        suite.set_price("BTC/USD", 1_000, 1);
+        assert!(cumulative(&suite).is_err());
+        record(&mut suite);
+        let first = cumulative(&suite).unwrap();
+        assert_eq!((first.cumulative, first.price), (SignedDecimal256::zero(), decimal("100")));
+
+        // 100 for 10 seconds, then 130 for 20 seconds
+        suite.advance(10);
+        suite.set_price("BTC/USD", 1_300, 1);
+        record(&mut suite);
+        suite.advance(20);
+        let second = cumulative(&suite).unwrap();
+        assert_eq!(second.cumulative, decimal("3600"));
+        assert_eq!(second.last_updated.seconds() + 20, second.block_time.seconds());
+
+        let elapsed = second.block_time.seconds() - first.block_time.seconds();
+        let twap = (second.cumulative - first.cumulative) / SignedDecimal256::from_ratio(elapsed, 1u64);
+        assert_eq!(twap, decimal("120"));
+
+        // reads do not record, the accumulator keeps accruing the latest price
+        suite.advance(10);
+        assert_eq!(cumulative(&suite).unwrap().cumulative, decimal("4900"));
+    }
+
+    #[test]
+    fn negative_prices_accrue() {
+        let mut suite = Suite::new();
+        suite.set_price("BTC/USD", -5, 0);
+        record(&mut suite);
+        suite.advance(4);
+        assert_eq!(cumulative(&suite).unwrap().cumulative, decimal("-20"));
+    }
+
+    #[test]
+    fn paused_pairs_are_not_served() {
+        let mut suite = Suite::new();
+        suite.set_price("BTC/USD", 100, 0);
+        record(&mut suite);
+        let target = PauseTarget::Pair { pair_id: "BTC/USD".parse().unwrap() };
+        suite.execute(ADMIN, &ExecuteMsg::Pause { target: target.clone() }, &[]).unwrap();
+        assert!(cumulative(&suite).unwrap_err().to_string().contains("Paused"));
+        suite.execute(ADMIN, &ExecuteMsg::Unpause { target }, &[]).unwrap();
+        cumulative(&suite).unwrap();
+    }
+}
@@ -0,0 +1,191 @@
+use std::fmt;
+use std::str::FromStr;
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::error::ContractError;
+
+// slinky's limit, DeFi tickers like "PEPE,UNISWAP_V3,0X6982508145454CE325DDBE47A25D4EC3D2311933" are long
+const MAX_TICKER_LENGTH: usize = 256;
+
+/// A validated {Base}/{Quote} pair with upper case tickers.
+///
+/// Accepted as either a `"BASE/QUOTE"` string or a `{ "base", "quote" }` object,
+/// always serialized as the object.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, JsonSchema, Debug)]
+#[serde(rename_all = "snake_case", try_from = "CurrencyPairInput")]
+pub struct CurrencyPair {
+    base: String,
+    quote: String,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum CurrencyPairInput {
+    Id(String),
+    Parts { base: String, quote: String },
+}
+
+impl CurrencyPair {
+    pub fn new(base: &str, quote: &str) -> Result<Self, ContractError> {
+        Ok(CurrencyPair {
+            base: normalize_ticker(base)?,
+            quote: normalize_ticker(quote)?,
+        })
+    }
+
+    pub fn base(&self) -> &str {
+        &self.base
+    }
+
+    pub fn quote(&self) -> &str {
+        &self.quote
+    }
+}
+
+impl FromStr for CurrencyPair {
+    type Err = ContractError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (base, quote) = s.split_once('/').ok_or_else(|| ContractError::InvalidCurrencyPair {
+            pair: s.to_string(),
+            reason: "expected {Base}/{Quote}".to_string(),
+        })?;
+        CurrencyPair::new(base, quote)
+    }
+}
+
+impl TryFrom<CurrencyPairInput> for CurrencyPair {
+    type Error = ContractError;
+
+    fn try_from(input: CurrencyPairInput) -> Result<Self, Self::Error> {
+        match input {
+            CurrencyPairInput::Id(id) => id.parse(),
+            CurrencyPairInput::Parts { base, quote } => CurrencyPair::new(&base, &quote),
+        }
+    }
+}
+
+impl fmt::Display for CurrencyPair {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.base, self.quote)
+    }
+}
+
+// drops repeated pairs, keeping the first occurrence
+pub fn dedup_pairs(pairs: Vec<CurrencyPair>) -> Vec<CurrencyPair> {
+    let mut unique: Vec<CurrencyPair> = Vec::with_capacity(pairs.len());
+    for pair in pairs {
+        if !unique.contains(&pair) {
+            unique.push(pair);
+        }
+    }
+    unique
+}
+
+/// Validated, upper cased ticker, e.g. "bitcoin" is "BITCOIN".
+pub fn normalize_ticker(ticker: &str) -> Result<String, ContractError> {
+    let ticker = ticker.trim();
+    let invalid = |reason: &str| ContractError::InvalidCurrencyPair { pair: ticker.to_string(), reason: reason.to_string() };
+    if ticker.is_empty() {
+        return Err(invalid("empty ticker"));
+    }
+    if ticker.len() > MAX_TICKER_LENGTH {
+        return Err(invalid("ticker is too long"));
+    }
+    if !ticker.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | ',')) {
+        return Err(invalid("tickers may only contain letters, digits, '-', '_', '.' and ','"));
+    }
+    // DeFi tickers are {Symbol},{Venue},{Address}
+    if ticker.split(',').any(str::is_empty) {
+        return Err(invalid("empty ticker segment"));
+    }
+    Ok(ticker.to_ascii_uppercase())
+}
+
+#[cfg(test)]
+mod tests {
+    use cosmwasm_std::{from_json, to_json_string};
+
+    use super::*;
+    use crate::msgs::{ExecuteMsg, QueryMsg, SudoMsg};
+    use crate::query::ValuePortfolioResponse;
+    use crate::state::DenomMetadata;
+    use crate::testing::{attribute, error_of, Suite, ADMIN};
+
+    const DEFI: &str = "PEPE,UNISWAP_V3,0X6982508145454CE325DDBE47A25D4EC3D2311933";
+
+    #[test]
+    fn parse_and_display() {
+        let pair: CurrencyPair = " btc / usd ".parse().unwrap();
+        assert_eq!((pair.base(), pair.quote()), ("BTC", "USD"));
+        assert_eq!(pair.to_string(), "BTC/USD");
+        assert_eq!(CurrencyPair::new("Eth", "usdt").unwrap().to_string(), "ETH/USDT");
+
+        let defi: CurrencyPair = format!("{}/usd", DEFI.to_lowercase()).parse().unwrap();
+        assert_eq!(defi.base(), DEFI);
+        assert_eq!(normalize_ticker(&"A".repeat(MAX_TICKER_LENGTH)).unwrap().len(), MAX_TICKER_LENGTH);
+    }
+
+    #[test]
+    fn invalid_pairs() {
+        for (pair, reason) in [
+            ("BTCUSD", "expected {Base}/{Quote}"),
+            ("/USD", "empty ticker"),
+            ("BTC/US D", "tickers may only contain letters, digits, '-', '_', '.' and ','"),
+            ("BTC/USD/EUR", "tickers may only contain letters, digits, '-', '_', '.' and ','"),
+            ("PEPE,,0X69/USD", "empty ticker segment"),
+            ("PEPE,UNISWAP_V3,/USD", "empty ticker segment"),
+        ] {
+            let err = pair.parse::<CurrencyPair>().unwrap_err();
+            assert!(err.to_string().ends_with(reason), "{}: {}", pair, err);
+        }
+        let err = normalize_ticker(&"A".repeat(MAX_TICKER_LENGTH + 1)).unwrap_err();
+        assert!(err.to_string().ends_with("ticker is too long"));
+    }
+
+    #[test]
+    fn serde_forms() {
+        let from_string: CurrencyPair = from_json(br#""btc/usd""#).unwrap();
+        let from_object: CurrencyPair = from_json(br#"{"base":"BTC","quote":"usd"}"#).unwrap();
+        assert_eq!(from_string, from_object);
+        assert_eq!(to_json_string(&from_string).unwrap(), r#"{"base":"BTC","quote":"USD"}"#);
+        assert!(from_json::<CurrencyPair>(br#"{"base":"BTC","quote":""}"#).is_err());
+        assert!(from_json::<ExecuteMsg>(br#"{"pause":{"target":{"pair":{"pair_id":"BTC USD"}}}}"#).is_err());
+
+        let pairs = ["BTC/USD", "ETH/USD", "btc/usd"].iter().map(|pair| pair.parse().unwrap()).collect();
+        assert_eq!(dedup_pairs(pairs).iter().map(ToString::to_string).collect::<Vec<_>>(), vec!["BTC/USD", "ETH/USD"]);
+    }
+
+    #[test]
+    fn tickers_are_validated_on_every_input() {
+        let mut suite = Suite::new();
+        suite.set_price("BTC/USD", 100, 0);
+
+        let msg = ExecuteMsg::RegisterDenom { denom: "ubtc".to_string(), symbol: "btc".to_string(), decimals: 6 };
+        suite.execute(ADMIN, &msg, &[]).unwrap();
+        let metadata: DenomMetadata = suite.query(&QueryMsg::GetDenomMetadata { denom: "ubtc".to_string() }).unwrap();
+        assert_eq!(metadata.symbol, "BTC");
+        let msg = ExecuteMsg::RegisterDenom { denom: "ubad".to_string(), symbol: "B T C".to_string(), decimals: 6 };
+        assert!(error_of(suite.execute(ADMIN, &msg, &[]).unwrap_err()).starts_with("Invalid currency pair B T C"));
+
+        let res: ValuePortfolioResponse = suite
+            .query(&QueryMsg::ValuePortfolio { coins: vec![cosmwasm_std::coin(1_000_000, "ubtc")], quote: "usd".to_string() })
+            .unwrap();
+        assert_eq!(res.total_value.to_string(), "100");
+
+        let msg = ExecuteMsg::RegisterPythFeed { id: "0xbtc".to_string(), base: "btc".to_string(), quote: "usd".to_string(), ema_half_life: None };
+        let res = suite.execute(ADMIN, &msg, &[]).unwrap();
+        assert_eq!(attribute(&res, "base"), "BTC");
+        // the ema reported with the price needs a recorded price
+        suite.execute(ADMIN, &ExecuteMsg::QuoteAndRecord { pairs: vec!["BTC/USD".parse().unwrap()] }, &[]).unwrap();
+        suite.query::<cosmwasm_std::Empty>(&QueryMsg::PriceFeed { id: "0xbtc".to_string() }).unwrap();
+        let msg = ExecuteMsg::RegisterPythFeed { id: "0xbad".to_string(), base: "btc".to_string(), quote: "".to_string(), ema_half_life: None };
+        assert!(suite.execute(ADMIN, &msg, &[]).is_err());
+
+        assert!(suite.sudo(&SudoMsg::SetConfig { admin: None, quote: Some("U$D".to_string()) }).is_err());
+        let res = suite.sudo(&SudoMsg::SetConfig { admin: None, quote: Some("eur".to_string()) }).unwrap();
+        assert_eq!(attribute(&res, "quote"), "EUR");
+    }
+}
@@ -0,0 +1,120 @@
+use cosmwasm_std::Timestamp;
+use serde::de::Error;
+use serde::{Deserialize, Deserializer};
+
+use crate::timestamp::convert_iso_string_to_timestamp;
+
+// stargate responses are marshalled by the node, which may encode 64 bit integers
+// either as json numbers or as strings, and google.protobuf.Timestamp either as
+// an RFC 3339 string or as a {seconds, nanos} object
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum U64Repr {
+    Number(u64),
+    String(String),
+}
+
+impl U64Repr {
+    fn into_u64<E: Error>(self) -> Result<u64, E> {
+        match self {
+            U64Repr::Number(n) => Ok(n),
+            U64Repr::String(s) => s.parse().map_err(|_| E::custom(format!("invalid uint64 {}", s))),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum TimestampRepr {
+    /// RFC 3339, or unix nanoseconds as serialized by cosmwasm's `Timestamp`
+    String(String),
+    Object {
+        seconds: U64Repr,
+        #[serde(default)]
+        nanos: u32,
+    },
+}
+
+/// uint64 encoded as a json number or a string
+pub fn deserialize_u64<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u64, D::Error> {
+    U64Repr::deserialize(deserializer)?.into_u64()
+}
+
+/// google.protobuf.Timestamp encoded as an RFC 3339 string or a {seconds, nanos} object
+pub fn deserialize_timestamp<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Timestamp, D::Error> {
+    match TimestampRepr::deserialize(deserializer)? {
+        TimestampRepr::String(s) if !s.is_empty() && s.bytes().all(|b| b.is_ascii_digit()) => s
+            .parse()
+            .map(Timestamp::from_nanos)
+            .map_err(|_| D::Error::custom(format!("invalid timestamp {}", s))),
+        TimestampRepr::String(s) => convert_iso_string_to_timestamp(&s).map_err(D::Error::custom),
+        TimestampRepr::Object { seconds, nanos } => {
+            if nanos >= 1_000_000_000 {
+                return Err(D::Error::custom(format!("invalid timestamp nanos {}", nanos)));
+            }
+            let seconds = seconds.into_u64()?;
+            seconds
+                .checked_mul(1_000_000_000)
+                .and_then(|ns| ns.checked_add(nanos as u64))
+                .map(Timestamp::from_nanos)
+                .ok_or_else(|| D::Error::custom(format!("timestamp {} seconds out of range", seconds)))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cosmwasm_std::{from_json, StdResult};
+    use serde::Deserialize;
+
+    use super::*;
+
+    #[derive(Deserialize, Debug)]
+    struct Raw {
+        #[serde(deserialize_with = "deserialize_u64")]
+        nonce: u64,
+        #[serde(deserialize_with = "deserialize_timestamp")]
+        block_timestamp: Timestamp,
+    }
+
+    fn raw(json: &str) -> StdResult<Raw> {
+        from_json(json.as_bytes())
+    }
+
+    #[test]
+    fn uint64_as_number_or_string() {
+        let timestamp = r#""1970-01-01T00:00:01Z""#;
+        assert_eq!(raw(&format!(r#"{{"nonce":7,"block_timestamp":{}}}"#, timestamp)).unwrap().nonce, 7);
+        assert_eq!(raw(&format!(r#"{{"nonce":"18446744073709551615","block_timestamp":{}}}"#, timestamp)).unwrap().nonce, u64::MAX);
+        assert!(raw(&format!(r#"{{"nonce":"18446744073709551616","block_timestamp":{}}}"#, timestamp)).is_err());
+        assert!(raw(&format!(r#"{{"nonce":"-1","block_timestamp":{}}}"#, timestamp)).is_err());
+        assert!(raw(&format!(r#"{{"nonce":1.5,"block_timestamp":{}}}"#, timestamp)).is_err());
+    }
+
+    #[test]
+    fn timestamp_forms() {
+        let expected = Timestamp::from_nanos(1_716_190_000_123_000_000);
+        for timestamp in [
+            r#""2024-05-20T07:26:40.123Z""#,
+            r#""1716190000123000000""#,
+            r#"{"seconds":"1716190000","nanos":123000000}"#,
+            r#"{"seconds":1716190000,"nanos":123000000}"#,
+        ] {
+            let res = raw(&format!(r#"{{"nonce":0,"block_timestamp":{}}}"#, timestamp)).unwrap();
+            assert_eq!(res.block_timestamp, expected, "{}", timestamp);
+        }
+        let res = raw(r#"{"nonce":0,"block_timestamp":{"seconds":"5"}}"#).unwrap();
+        assert_eq!(res.block_timestamp, Timestamp::from_seconds(5));
+
+        for invalid in [
+            r#""2024-02-31T00:00:00Z""#,
+            r#""""#,
+            r#"{"seconds":"1","nanos":1000000000}"#,
+            r#"{"seconds":"18446744073709551615"}"#,
+            r#"true"#,
+        ] {
+            assert!(raw(&format!(r#"{{"nonce":0,"block_timestamp":{}}}"#, invalid)).is_err(), "{}", invalid);
+        }
+    }
+}
@@ -0,0 +1,104 @@
+use cosmwasm_std::{OverflowError, StdError};
+use thiserror::Error;
+
+#[derive(Error, Debug, PartialEq)]
+pub enum ContractError {
+  #[error("{0}")]
+  Std(#[from] StdError),
+
+  #[error("{0}")]
+  Overflow(#[from] OverflowError),
+
+  #[error("Unauthorized")]
+  Unauthorized {},
+
+  #[error("Paused: {target}")]
+  Paused { target: String },
+
+  #[error("Unknown query kind {kind}")]
+  UnknownQueryKind { kind: String },
+
+  #[error("At least one oracle source is required")]
+  EmptyOracleSources {},
+
+  #[error("fresh_seconds must not exceed stale_seconds")]
+  InvalidFreshnessThresholds {},
+
+  #[error("Invalid currency pair {pair}: {reason}")]
+  InvalidCurrencyPair { pair: String, reason: String },
+
+  #[error("At least one currency pair is required")]
+  EmptyPairs {},
+
+  #[error("Invalid pair bounds: {reason}")]
+  InvalidPairBounds { reason: String },
+
+  #[error("Price of {pair} out of bounds: {reason}")]
+  PriceOutOfBounds { pair: String, reason: String },
+
+  #[error("At most {max} currency pairs are allowed")]
+  TooManyPairs { max: usize },
+
+  #[error("At most {max} subscriptions per owner are allowed")]
+  TooManySubscriptions { max: u32 },
+
+  #[error("No funds sent")]
+  NoFunds {},
+
+  #[error("Only supports channel with ibc version slinky-price-1, got {version}")]
+  InvalidIbcVersion { version: String },
+
+  #[error("Only supports unordered channel")]
+  OnlyUnorderedChannel {},
+
+  #[error("Cannot migrate from contract {name}")]
+  InvalidContractName { name: String },
+
+  #[error("Invalid contract version {version}")]
+  InvalidVersion { version: String },
+
+  #[error("Cannot downgrade contract from {from} to {to}")]
+  Downgrade { from: String, to: String },
+
+  #[error("Admin is required to migrate an unversioned contract")]
+  MissingAdmin {},
+
+  #[error("Unknown reply id {id}")]
+  UnknownReplyId { id: u64 },
+
+  #[error("At least one action is required")]
+  EmptyOrderActions {},
+
+  #[error("At least one order id is required")]
+  EmptyOrderIds {},
+
+  #[error("Expiry must be in the future")]
+  InvalidExpiry {},
+
+  #[error("twap_window must be positive")]
+  InvalidTwapWindow {},
+
+  #[error("Orders cannot execute this contract")]
+  UnsupportedOrderAction {},
+
+  #[error("Order actions spend more than the escrowed funds")]
+  InsufficientEscrow {},
+
+  #[error("settle_at must be in the future")]
+  InvalidSettleAt {},
+
+  #[error("No price at or after settle_at for settlement {id} yet")]
+  SettlementNotDue { id: u64 },
+
+  #[error("Settlement {id} is already finalized")]
+  SettlementFinalized { id: u64 },
+
+  #[error("Settlement max_delay must be positive and at most {max} seconds")]
+  InvalidSettlementDelay { max: u64 },
+
+  #[error("More than {max} observations of {pair} in the window")]
+  TooManyObservations { pair: String, max: u64 },
+
+  #[error("Candle intervals must be positive and at most {max_interval} seconds, at most {max} of them")]
+  InvalidCandleIntervals { max: usize, max_interval: u64 },
+}
@@ -0,0 +1,131 @@
+use cosmwasm_std::Event;
+
+use crate::error::ContractError;
+use crate::query::GetPriceResponse;
+use crate::timestamp::convert_timestamp_to_iso_string;
+
+// emitted as `wasm-slinky_price`
+pub const PRICE_EVENT_TYPE: &str = "slinky_price";
+// emitted as `wasm-slinky_price_rejected`
+pub const PRICE_REJECTED_EVENT_TYPE: &str = "slinky_price_rejected";
+
+pub fn price_event(action: &str, pair_id: &str, price: &GetPriceResponse) -> Event {
+    Event::new(PRICE_EVENT_TYPE)
+        .add_attribute("action", action)
+        .add_attribute("pair", pair_id)
+        .add_attribute("price", price.price.price.to_string())
+        .add_attribute("decimals", price.decimals.to_string())
+        .add_attribute("nonce", price.nonce.to_string())
+        .add_attribute("height", price.price.block_height.to_string())
+        .add_attribute("timestamp", convert_timestamp_to_iso_string(&price.price.block_timestamp))
+}
+
+pub fn price_events(action: &str, pair_ids: &[String], prices: &[GetPriceResponse]) -> Vec<Event> {
+    pair_ids
+        .iter()
+        .zip(prices.iter())
+        .map(|(pair_id, price)| price_event(action, pair_id, price))
+        .collect()
+}
+
+// the circuit breaker trip behind `err`, none for any other error
+pub fn price_rejected_event(action: &str, err: &ContractError) -> Option<Event> {
+    match err {
+        ContractError::PriceOutOfBounds { pair, reason } => Some(
+            Event::new(PRICE_REJECTED_EVENT_TYPE)
+                .add_attribute("action", action)
+                .add_attribute("pair", pair)
+                .add_attribute("reason", reason),
+        ),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cosmwasm_std::SignedDecimal256;
+    use cw_multi_test::AppResponse;
+
+    use crate::bounds::PairBounds;
+    use crate::msgs::ExecuteMsg;
+    use crate::testing::{mock_subscriber, Suite, ADMIN, KEEPER, USER};
+
+    // attributes of every `wasm-slinky_price` event
+    fn price_events(res: &AppResponse) -> Vec<Vec<(String, String)>> {
+        res.events
+            .iter()
+            .filter(|event| event.ty == "wasm-slinky_price")
+            .map(|event| event.attributes.iter().map(|attr| (attr.key.clone(), attr.value.clone())).collect())
+            .collect()
+    }
+
+    #[test]
+    fn poke_emits_a_price_event_per_pair() {
+        let mut suite = Suite::new();
+        suite.set_price("BTC/USD", -6942640000000, 8);
+        suite.set_price("ETH/USD", 350000, 2);
+        let subscriber = mock_subscriber(&mut suite, false);
+        let msg = ExecuteMsg::Subscribe {
+            pairs: vec!["BTC/USD".parse().unwrap(), "ETH/USD".parse().unwrap()],
+            contract: subscriber.to_string(),
+            min_interval: 0,
+            deviation_bps: 0,
+        };
+        suite.execute(USER, &msg, &[]).unwrap();
+
+        let res = suite.execute(KEEPER, &ExecuteMsg::Poke { limit: None }, &[]).unwrap();
+        let events = price_events(&res);
+        assert_eq!(events.len(), 2);
+        let expected = [
+            ("_contract_address", suite.contract.as_str()),
+            ("action", "poke"),
+            ("pair", "BTC/USD"),
+            ("price", "-6942640000000"),
+            ("decimals", "8"),
+            ("nonce", "1"),
+            ("height", "12345"),
+            ("timestamp", "2019-10-23T02:23:39.879305533Z"),
+        ];
+        assert_eq!(events[0], expected.map(|(key, value)| (key.to_string(), value.to_string())));
+        assert_eq!(events[1][2], ("pair".to_string(), "ETH/USD".to_string()));
+    }
+
+    #[test]
+    fn poke_emits_an_event_per_rejected_pair() {
+        let mut suite = Suite::new();
+        suite.set_price("BTC/USD", 100, 0);
+        suite.set_price("ETH/USD", 10, 0);
+        let subscriber = mock_subscriber(&mut suite, false);
+        let msg = ExecuteMsg::Subscribe {
+            pairs: vec!["BTC/USD".parse().unwrap(), "ETH/USD".parse().unwrap()],
+            contract: subscriber.to_string(),
+            min_interval: 0,
+            deviation_bps: 0,
+        };
+        suite.execute(USER, &msg, &[]).unwrap();
+        let bounds = PairBounds { min_price: Some(SignedDecimal256::percent(20_000)), max_price: None, max_deviation_bps: None, reference_price: None };
+        suite.execute(ADMIN, &ExecuteMsg::SetPairBounds { pair: "BTC/USD".parse().unwrap(), bounds: Some(bounds) }, &[]).unwrap();
+
+        let res = suite.execute(KEEPER, &ExecuteMsg::Poke { limit: None }, &[]).unwrap();
+        assert_eq!(price_events(&res).len(), 1);
+        let rejected: Vec<Vec<(String, String)>> = res.events
+            .iter()
+            .filter(|event| event.ty == "wasm-slinky_price_rejected")
+            .map(|event| event.attributes.iter().map(|attr| (attr.key.clone(), attr.value.clone())).collect())
+            .collect();
+        let expected = [
+            ("_contract_address", suite.contract.as_str()),
+            ("action", "poke"),
+            ("pair", "BTC/USD"),
+            ("reason", "100 is below min_price 200"),
+        ];
+        assert_eq!(rejected, vec![expected.map(|(key, value)| (key.to_string(), value.to_string())).to_vec()]);
+    }
+
+    #[test]
+    fn no_event_without_prices() {
+        let mut suite = Suite::new();
+        let res = suite.execute(KEEPER, &ExecuteMsg::Poke { limit: None }, &[]).unwrap();
+        assert!(price_events(&res).is_empty());
+    }
+}
@@ -1,28 +1,297 @@
-use cosmwasm_std::{DepsMut, Env, MessageInfo, Response, StdResult};
-use crate::msgs::{InstantiateMsg, ExecuteMsg};
-use crate::state::Contract;
+use cosmwasm_std::{DepsMut, Env, MessageInfo, Reply, Response, StdError};
+use cw2::set_contract_version;
+use crate::compat::DEFAULT_PYTH_EMA_HALF_LIFE;
+use crate::currency_pair::{normalize_ticker, CurrencyPair};
+use crate::error::ContractError;
+use crate::migrate::{CONTRACT_NAME, CONTRACT_VERSION};
+use crate::msgs::{InstantiateMsg, ExecuteMsg, SudoMsg};
+use crate::oracle_source::OracleSourceConfig;
+use crate::subscription::SUBSCRIPTION_CALLBACK_REPLY_ID;
+use crate::state::{Config, Contract, DenomMetadata, FeedPair, FreshnessThresholds};
 
-impl<'a> Contract {
+impl<'a> Contract<'a> {
     pub fn instantiate(
         &self,
-        _deps: DepsMut,
+        deps: DepsMut,
         _env: Env,
-        _info: MessageInfo,
-        _msg: InstantiateMsg
-    ) -> StdResult<Response> {
-        Ok(Response::new())
+        info: MessageInfo,
+        msg: InstantiateMsg
+    ) -> Result<Response, ContractError> {
+        let admin = match msg.admin {
+            Some(admin) => deps.api.addr_validate(&admin)?,
+            None => info.sender,
+        };
+        let quote = normalize_ticker(msg.quote.as_deref().unwrap_or("USD"))?;
+        self.config.save(deps.storage, &Config { admin: admin.clone(), quote })?;
+        set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+        Ok(Response::new()
+            .add_attribute("action", "instantiate")
+            .add_attribute("admin", admin))
     }
 
     pub fn execute(
         &self,
-        _deps: DepsMut,
-        _env: Env,
-        _info: MessageInfo,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
         msg: ExecuteMsg
-    ) -> StdResult<Response> {
+    ) -> Result<Response, ContractError> {
+        match msg {
+            ExecuteMsg::RegisterDenom { denom, symbol, decimals }
+                => self.register_denom(deps, env, info, denom, symbol, decimals),
+            ExecuteMsg::RemoveDenom { denom }
+                => self.remove_denom(deps, env, info, denom),
+            ExecuteMsg::SetOracleSources { sources } => {
+                self.assert_admin(&deps, &info)?;
+                self.set_oracle_sources(deps, env, sources)
+            }
+            ExecuteMsg::RegisterPythFeed { id, base, quote, ema_half_life }
+                => self.register_pyth_feed(deps, env, info, id, base, quote, ema_half_life),
+            ExecuteMsg::RemovePythFeed { id }
+                => self.remove_pyth_feed(deps, env, info, id),
+            ExecuteMsg::Subscribe { pairs, contract, min_interval, deviation_bps }
+                => self.subscribe(deps, env, info, pairs, contract, min_interval, deviation_bps),
+            ExecuteMsg::Unsubscribe { id }
+                => self.unsubscribe(deps, env, info, id),
+            ExecuteMsg::RemoveChannelSubscription { channel_id } => {
+                self.assert_admin(&deps, &info)?;
+                self.remove_channel_subscription(deps, env, channel_id)
+            }
+            ExecuteMsg::Poke { limit }
+                => self.poke(deps, env, info, limit),
+            ExecuteMsg::SetKeeperReward { reward } => {
+                self.assert_admin(&deps, &info)?;
+                self.set_keeper_reward(deps, env, reward)
+            }
+            ExecuteMsg::SetPairBounds { pair, bounds } => {
+                self.assert_admin(&deps, &info)?;
+                self.set_pair_bounds(deps, env, pair, bounds)
+            }
+            ExecuteMsg::FundRewardPool {}
+                => self.fund_reward_pool(deps, env, info),
+            ExecuteMsg::WithdrawRewardPool { amount }
+                => self.withdraw_reward_pool(deps, env, info, amount),
+            ExecuteMsg::SetGuardians { guardians }
+                => self.set_guardians(deps, env, info, guardians),
+            ExecuteMsg::Pause { target }
+                => self.pause(deps, env, info, target),
+            ExecuteMsg::Unpause { target }
+                => self.unpause(deps, env, info, target),
+            ExecuteMsg::SetFreshnessThresholds { thresholds } => {
+                self.assert_admin(&deps, &info)?;
+                self.set_freshness_thresholds(deps, env, thresholds)
+            }
+            ExecuteMsg::CreateOrder { pair, condition, threshold, twap_window, actions, expires_at }
+                => self.create_order(deps, env, info, pair, condition, threshold, twap_window, actions, expires_at),
+            ExecuteMsg::CancelOrder { id }
+                => self.cancel_order(deps, env, info, id),
+            ExecuteMsg::Trigger { ids }
+                => self.trigger(deps, env, info, ids),
+            ExecuteMsg::RegisterSettlement { pair, settle_at, max_delay }
+                => self.register_settlement(deps, env, info, pair, settle_at, max_delay),
+            ExecuteMsg::FinalizeSettlement { id }
+                => self.finalize_settlement(deps, env, info, id),
+            ExecuteMsg::QuoteAndRecord { pairs }
+                => self.quote_and_record(deps, env, info, pairs),
+            ExecuteMsg::SetCandleIntervals { intervals } => {
+                self.assert_admin(&deps, &info)?;
+                self.set_candle_intervals(deps, env, intervals)
+            }
+        }
+    }
+}
+
+impl<'a> Contract<'a> {
+    pub fn sudo(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        msg: SudoMsg
+    ) -> Result<Response, ContractError> {
         match msg {
-            ExecuteMsg::Foo {}
-                => Ok(Response::new())
+            SudoMsg::SetConfig { admin, quote }
+                => self.set_config(deps, env, admin, quote),
+            SudoMsg::SetPairBounds { pair, bounds }
+                => self.set_pair_bounds(deps, env, pair, bounds),
+            SudoMsg::SetOracleSources { sources }
+                => self.set_oracle_sources(deps, env, sources),
+            SudoMsg::SetKeeperReward { reward }
+                => self.set_keeper_reward(deps, env, reward),
+            SudoMsg::ForceUnpause {}
+                => self.force_unpause(deps, env),
+            SudoMsg::RemoveChannelSubscription { channel_id }
+                => self.remove_channel_subscription(deps, env, channel_id),
+            SudoMsg::SetFreshnessThresholds { thresholds }
+                => self.set_freshness_thresholds(deps, env, thresholds),
+            SudoMsg::SetCandleIntervals { intervals }
+                => self.set_candle_intervals(deps, env, intervals),
+        }
+    }
+}
+
+impl<'a> Contract<'a> {
+    pub fn reply(
+        &self,
+        _deps: DepsMut,
+        _env: Env,
+        msg: Reply,
+    ) -> Result<Response, ContractError> {
+        match msg.id {
+            SUBSCRIPTION_CALLBACK_REPLY_ID => Ok(Response::new()
+                .add_attribute("action", "subscription_callback_failed")
+                .add_attribute("error", msg.result.unwrap_err())),
+            id => Err(ContractError::UnknownReplyId { id }),
+        }
+    }
+}
+
+impl<'a> Contract<'a> {
+    fn set_config(
+        &self,
+        deps: DepsMut,
+        _env: Env,
+        admin: Option<String>,
+        quote: Option<String>,
+    ) -> Result<Response, ContractError> {
+        let mut config = self.config.load(deps.storage)?;
+        if let Some(admin) = admin {
+            config.admin = deps.api.addr_validate(&admin)?;
+        }
+        if let Some(quote) = quote {
+            config.quote = normalize_ticker(&quote)?;
+        }
+        self.config.save(deps.storage, &config)?;
+
+        Ok(Response::new()
+            .add_attribute("action", "set_config")
+            .add_attribute("admin", config.admin)
+            .add_attribute("quote", config.quote))
+    }
+
+    fn register_denom(
+        &self,
+        deps: DepsMut,
+        _env: Env,
+        info: MessageInfo,
+        denom: String,
+        symbol: String,
+        decimals: u32,
+    ) -> Result<Response, ContractError> {
+        self.assert_admin(&deps, &info)?;
+        let symbol = normalize_ticker(&symbol)?;
+        self.denoms.save(deps.storage, &denom, &DenomMetadata { symbol: symbol.clone(), decimals })?;
+
+        Ok(Response::new()
+            .add_attribute("action", "register_denom")
+            .add_attribute("denom", denom)
+            .add_attribute("symbol", symbol)
+            .add_attribute("decimals", decimals.to_string()))
+    }
+
+    fn remove_denom(
+        &self,
+        deps: DepsMut,
+        _env: Env,
+        info: MessageInfo,
+        denom: String,
+    ) -> Result<Response, ContractError> {
+        self.assert_admin(&deps, &info)?;
+        self.denoms.remove(deps.storage, &denom);
+
+        Ok(Response::new()
+            .add_attribute("action", "remove_denom")
+            .add_attribute("denom", denom))
+    }
+
+    // authorized by the caller, shared by execute and sudo
+    fn set_oracle_sources(
+        &self,
+        deps: DepsMut,
+        _env: Env,
+        sources: Vec<OracleSourceConfig>,
+    ) -> Result<Response, ContractError> {
+        if sources.is_empty() {
+            return Err(ContractError::EmptyOracleSources {});
+        }
+        for source in sources.iter() {
+            if let OracleSourceConfig::PriceFeedContract { contract_addr } = source {
+                deps.api.addr_validate(contract_addr)?;
+            }
+        }
+        self.oracle_sources.save(deps.storage, &sources)?;
+
+        Ok(Response::new()
+            .add_attribute("action", "set_oracle_sources")
+            .add_attribute("count", sources.len().to_string()))
+    }
+
+    // authorized by the caller, shared by execute and sudo
+    fn set_freshness_thresholds(
+        &self,
+        deps: DepsMut,
+        _env: Env,
+        thresholds: FreshnessThresholds,
+    ) -> Result<Response, ContractError> {
+        if thresholds.fresh_seconds > thresholds.stale_seconds {
+            return Err(ContractError::InvalidFreshnessThresholds {});
+        }
+        self.freshness_thresholds.save(deps.storage, &thresholds)?;
+
+        Ok(Response::new()
+            .add_attribute("action", "set_freshness_thresholds")
+            .add_attribute("fresh_seconds", thresholds.fresh_seconds.to_string())
+            .add_attribute("stale_seconds", thresholds.stale_seconds.to_string())
+            .add_attribute("nonce_window_blocks", thresholds.nonce_window_blocks.to_string()))
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn register_pyth_feed(
+        &self,
+        deps: DepsMut,
+        _env: Env,
+        info: MessageInfo,
+        id: String,
+        base: String,
+        quote: String,
+        ema_half_life: Option<u64>,
+    ) -> Result<Response, ContractError> {
+        self.assert_admin(&deps, &info)?;
+        let pair = CurrencyPair::new(&base, &quote)?;
+        let ema_half_life = ema_half_life.unwrap_or(DEFAULT_PYTH_EMA_HALF_LIFE);
+        if ema_half_life == 0 {
+            return Err(StdError::generic_err("ema_half_life must be positive").into());
+        }
+        self.pyth_feeds.save(deps.storage, &id, &FeedPair { base: pair.base().to_string(), quote: pair.quote().to_string(), ema_half_life })?;
+
+        Ok(Response::new()
+            .add_attribute("action", "register_pyth_feed")
+            .add_attribute("id", id)
+            .add_attribute("base", pair.base())
+            .add_attribute("quote", pair.quote())
+            .add_attribute("ema_half_life", ema_half_life.to_string()))
+    }
+
+    fn remove_pyth_feed(
+        &self,
+        deps: DepsMut,
+        _env: Env,
+        info: MessageInfo,
+        id: String,
+    ) -> Result<Response, ContractError> {
+        self.assert_admin(&deps, &info)?;
+        self.pyth_feeds.remove(deps.storage, &id);
+
+        Ok(Response::new()
+            .add_attribute("action", "remove_pyth_feed")
+            .add_attribute("id", id))
+    }
+
+    pub(crate) fn assert_admin(&self, deps: &DepsMut, info: &MessageInfo) -> Result<(), ContractError> {
+        let config = self.config.load(deps.storage)?;
+        if config.admin != info.sender {
+            return Err(ContractError::Unauthorized {});
         }
+        Ok(())
     }
 }
@@ -0,0 +1,495 @@
+use cosmwasm_std::{
+    from_json, to_json_binary, Binary, Deps, DepsMut, Env, Event, IbcBasicResponse, IbcChannel, IbcChannelCloseMsg,
+    IbcChannelConnectMsg, IbcChannelOpenMsg, IbcChannelOpenResponse, IbcMsg, IbcOrder, IbcPacketAckMsg,
+    IbcPacketReceiveMsg, IbcPacketTimeoutMsg, IbcReceiveResponse, IbcTimeout, Order, Response, StdError, StdResult, Storage,
+    Timestamp,
+};
+use cw_storage_plus::Bound;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::currency_pair::{dedup_pairs, CurrencyPair};
+use crate::error::ContractError;
+use crate::events::{price_events, price_rejected_event};
+use crate::query::GetPriceResponse;
+use crate::state::Contract;
+use crate::subscription::{PairPrice, MAX_SUBSCRIPTION_PAIRS};
+
+pub const IBC_VERSION: &str = "slinky-price-1";
+pub const IBC_ORDERING: IbcOrder = IbcOrder::Unordered;
+
+// seconds until a pushed price packet times out
+const PACKET_LIFETIME: u64 = 60 * 10;
+
+const DEFAULT_LIMIT: u32 = 10;
+const MAX_LIMIT: u32 = 30;
+
+/// Packets sent by a counterparty contract.
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum PriceRequestPacket {
+    /// acknowledged with a `GetPricesResponse`
+    GetPrices {
+        pair_ids: Vec<CurrencyPair>,
+    },
+    /// pushes `pair_ids` prices over the channel on `poke`, at most every `min_interval` seconds
+    Subscribe {
+        pair_ids: Vec<CurrencyPair>,
+        min_interval: u64,
+    },
+    Unsubscribe {},
+}
+
+/// Packet pushed to subscribed channels.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum PricePushPacket {
+    PriceUpdate {
+        prices: Vec<PairPrice>,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum PriceAck {
+    Result(Binary),
+    Error(String),
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct ChannelSubscription {
+    /// {Base}/{Quote}
+    pub pair_ids: Vec<String>,
+    pub min_interval: u64,
+    pub last_push: Option<Timestamp>,
+}
+
+impl<'a> Contract<'a> {
+    pub fn ibc_channel_open(
+        &self,
+        _deps: DepsMut,
+        _env: Env,
+        msg: IbcChannelOpenMsg,
+    ) -> Result<IbcChannelOpenResponse, ContractError> {
+        validate_channel(msg.channel(), msg.counterparty_version())
+    }
+
+    pub fn ibc_channel_connect(
+        &self,
+        _deps: DepsMut,
+        _env: Env,
+        msg: IbcChannelConnectMsg,
+    ) -> Result<IbcBasicResponse, ContractError> {
+        validate_channel(msg.channel(), msg.counterparty_version())?;
+
+        Ok(IbcBasicResponse::new()
+            .add_attribute("action", "ibc_channel_connect")
+            .add_attribute("channel_id", &msg.channel().endpoint.channel_id))
+    }
+
+    pub fn ibc_channel_close(
+        &self,
+        deps: DepsMut,
+        _env: Env,
+        msg: IbcChannelCloseMsg,
+    ) -> Result<IbcBasicResponse, ContractError> {
+        let channel_id = &msg.channel().endpoint.channel_id;
+        self.channel_subscriptions.remove(deps.storage, channel_id);
+
+        Ok(IbcBasicResponse::new()
+            .add_attribute("action", "ibc_channel_close")
+            .add_attribute("channel_id", channel_id))
+    }
+
+    pub fn ibc_packet_receive(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        msg: IbcPacketReceiveMsg,
+    ) -> Result<IbcReceiveResponse, ContractError> {
+        let channel_id = msg.packet.dest.channel_id.clone();
+        let res = from_json(&msg.packet.data)
+            .map_err(ContractError::from)
+            .and_then(|packet| self.handle_price_request(deps, env, &channel_id, packet));
+
+        // errors are acknowledged instead of aborting, so the counterparty learns about them
+        let (ack, events) = match res {
+            Ok((data, events)) => (PriceAck::Result(data), events),
+            Err(err) => {
+                let events = price_rejected_event("ibc_packet_receive", &err).into_iter().collect();
+                (PriceAck::Error(err.to_string()), events)
+            }
+        };
+
+        Ok(IbcReceiveResponse::new()
+            .add_attribute("action", "ibc_packet_receive")
+            .add_attribute("channel_id", channel_id)
+            .add_events(events)
+            .set_ack(to_json_binary(&ack)?))
+    }
+
+    pub fn ibc_packet_ack(
+        &self,
+        _deps: DepsMut,
+        _env: Env,
+        _msg: IbcPacketAckMsg,
+    ) -> Result<IbcBasicResponse, ContractError> {
+        Ok(IbcBasicResponse::new().add_attribute("action", "ibc_packet_ack"))
+    }
+
+    pub fn ibc_packet_timeout(
+        &self,
+        _deps: DepsMut,
+        _env: Env,
+        _msg: IbcPacketTimeoutMsg,
+    ) -> Result<IbcBasicResponse, ContractError> {
+        Ok(IbcBasicResponse::new().add_attribute("action", "ibc_packet_timeout"))
+    }
+
+    fn handle_price_request(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        channel_id: &str,
+        packet: PriceRequestPacket,
+    ) -> Result<(Binary, Vec<Event>), ContractError> {
+        match packet {
+            PriceRequestPacket::GetPrices { pair_ids } => {
+                let pair_ids: Vec<String> = dedup_pairs(pair_ids).iter().map(ToString::to_string).collect();
+                let res = self.get_prices(deps.as_ref(), env, pair_ids.clone())?;
+                let events = price_events("ibc_packet_receive", &pair_ids, &res.prices);
+                Ok((to_json_binary(&res)?, events))
+            }
+            PriceRequestPacket::Subscribe { pair_ids, min_interval } => {
+                if pair_ids.is_empty() {
+                    return Err(ContractError::EmptyPairs {});
+                }
+                let pair_ids: Vec<String> = dedup_pairs(pair_ids).iter().map(ToString::to_string).collect();
+                if pair_ids.len() > MAX_SUBSCRIPTION_PAIRS {
+                    return Err(ContractError::TooManyPairs { max: MAX_SUBSCRIPTION_PAIRS });
+                }
+                // fails on unknown pairs
+                self.get_prices(deps.as_ref(), env, pair_ids.clone())?;
+                self.channel_subscriptions.save(deps.storage, channel_id, &ChannelSubscription {
+                    pair_ids,
+                    min_interval,
+                    last_push: None,
+                })?;
+                Ok((Binary::default(), vec![]))
+            }
+            PriceRequestPacket::Unsubscribe {} => {
+                self.channel_subscriptions.remove(deps.storage, channel_id);
+                Ok((Binary::default(), vec![]))
+            }
+        }
+    }
+
+    pub(crate) fn remove_channel_subscription(
+        &self,
+        deps: DepsMut,
+        _env: Env,
+        channel_id: String,
+    ) -> Result<Response, ContractError> {
+        if !self.channel_subscriptions.has(deps.storage, &channel_id) {
+            return Err(StdError::not_found(format!("subscription of channel {}", channel_id)).into());
+        }
+        self.channel_subscriptions.remove(deps.storage, &channel_id);
+
+        Ok(Response::new()
+            .add_attribute("action", "remove_channel_subscription")
+            .add_attribute("channel_id", channel_id))
+    }
+
+    pub(crate) fn get_channel_subscriptions(
+        &self,
+        deps: Deps,
+        _env: Env,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    ) -> StdResult<ChannelSubscriptionsResponse> {
+        let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+        let subscriptions = self.channel_subscriptions
+            .range(deps.storage, start_after.as_deref().map(Bound::exclusive), None, Order::Ascending)
+            .take(limit)
+            .map(|item| item.map(|(channel_id, subscription)| ChannelSubscriptionResponse { channel_id, subscription }))
+            .collect::<StdResult<Vec<_>>>()?;
+
+        Ok(ChannelSubscriptionsResponse { subscriptions })
+    }
+
+    // up to `limit` channel subscriptions after `after`, wrapping around to the first ones
+    pub(crate) fn channel_subscription_page(
+        &self,
+        storage: &dyn Storage,
+        after: Option<String>,
+        limit: usize,
+    ) -> StdResult<Vec<(String, ChannelSubscription)>> {
+        let mut page = self.channel_subscriptions
+            .range(storage, after.as_deref().map(Bound::exclusive), None, Order::Ascending)
+            .take(limit)
+            .collect::<StdResult<Vec<_>>>()?;
+        if let Some(after) = after.as_deref().filter(|_| page.len() < limit) {
+            let wrapped = self.channel_subscriptions
+                .range(storage, None, Some(Bound::inclusive(after)), Order::Ascending)
+                .take(limit - page.len())
+                .collect::<StdResult<Vec<_>>>()?;
+            page.extend(wrapped);
+        }
+        Ok(page)
+    }
+
+    // builds price packets for channels whose subscription is due and marks them as pushed,
+    // `pair_ids` and `prices` are the pairs that could be priced
+    pub(crate) fn push_channel_prices(
+        &self,
+        storage: &mut dyn Storage,
+        env: &Env,
+        channels: Vec<(String, ChannelSubscription)>,
+        pair_ids: &[String],
+        prices: &[GetPriceResponse],
+    ) -> StdResult<Vec<IbcMsg>> {
+        let mut messages = vec![];
+        for (channel_id, mut subscription) in channels {
+            let due = subscription.last_push
+                .is_none_or(|last_push| env.block.time.seconds() >= last_push.seconds().saturating_add(subscription.min_interval));
+            if !due {
+                continue;
+            }
+
+            // pairs that failed to price are left out of the packet
+            let prices: Vec<PairPrice> = subscription.pair_ids
+                .iter()
+                .filter_map(|pair_id| {
+                    let i = pair_ids.iter().position(|id| id == pair_id)?;
+                    Some(PairPrice { pair_id: pair_id.clone(), price: prices[i].clone() })
+                })
+                .collect();
+            if prices.is_empty() {
+                continue;
+            }
+            let packet = PricePushPacket::PriceUpdate { prices };
+
+            subscription.last_push = Some(env.block.time);
+            self.channel_subscriptions.save(storage, &channel_id, &subscription)?;
+
+            messages.push(IbcMsg::SendPacket {
+                channel_id,
+                data: to_json_binary(&packet)?,
+                timeout: IbcTimeout::with_timestamp(env.block.time.plus_seconds(PACKET_LIFETIME)),
+            });
+        }
+        Ok(messages)
+    }
+}
+
+fn validate_channel(channel: &IbcChannel, counterparty_version: Option<&str>) -> Result<(), ContractError> {
+    if channel.version != IBC_VERSION {
+        return Err(ContractError::InvalidIbcVersion { version: channel.version.clone() });
+    }
+    if let Some(version) = counterparty_version {
+        if version != IBC_VERSION {
+            return Err(ContractError::InvalidIbcVersion { version: version.to_string() });
+        }
+    }
+    if channel.order != IBC_ORDERING {
+        return Err(ContractError::OnlyUnorderedChannel {});
+    }
+    Ok(())
+}
+
+#[derive(Clone, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct ChannelSubscriptionResponse {
+    pub channel_id: String,
+    pub subscription: ChannelSubscription,
+}
+
+#[derive(Clone, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct ChannelSubscriptionsResponse {
+    pub subscriptions: Vec<ChannelSubscriptionResponse>,
+}
+
+#[cfg(test)]
+mod tests {
+    use cosmwasm_std::testing::{
+        mock_env, mock_ibc_channel_close_confirm, mock_ibc_channel_connect_ack, mock_ibc_channel_open_init,
+        mock_ibc_packet_recv, mock_info, MockApi, MockStorage,
+    };
+    use cosmwasm_std::{CosmosMsg, OwnedDeps, SignedDecimal256};
+
+    use super::*;
+    use crate::entry;
+    use crate::msgs::{ExecuteMsg, InstantiateMsg, QueryMsg};
+    use crate::query::GetPricesResponse;
+    use crate::testing::{mock_deps, MockSlinky, OracleQuerier, ADMIN, KEEPER};
+
+    const CHANNEL: &str = "channel-7";
+
+    type Deps = OwnedDeps<MockStorage, MockApi, OracleQuerier>;
+
+    fn setup() -> (Deps, MockSlinky) {
+        let slinky = MockSlinky::default();
+        slinky.set_price("BTC/USD", 100, 0, &mock_env().block);
+        slinky.set_price("ETH/USD", 10, 0, &mock_env().block);
+        let mut deps = mock_deps(&slinky);
+        entry::instantiate(deps.as_mut(), mock_env(), mock_info(ADMIN, &[]), InstantiateMsg { admin: None, quote: None }).unwrap();
+        let channel = mock_ibc_channel_connect_ack(CHANNEL, IBC_ORDERING, IBC_VERSION);
+        entry::ibc_channel_connect(deps.as_mut(), mock_env(), channel).unwrap();
+        (deps, slinky)
+    }
+
+    fn receive(deps: &mut Deps, env: Env, packet: &PriceRequestPacket) -> PriceAck {
+        let msg = mock_ibc_packet_recv(CHANNEL, packet).unwrap();
+        let res = entry::ibc_packet_receive(deps.as_mut(), env, msg).unwrap();
+        from_json(res.acknowledgement).unwrap()
+    }
+
+    fn pairs(pair_ids: &[&str]) -> Vec<CurrencyPair> {
+        pair_ids.iter().map(|pair_id| pair_id.parse().unwrap()).collect()
+    }
+
+    // price packets sent by a poke at `env`
+    fn poke(deps: &mut Deps, env: Env) -> Vec<PricePushPacket> {
+        let res = entry::execute(deps.as_mut(), env, mock_info(KEEPER, &[]), ExecuteMsg::Poke { limit: None }).unwrap();
+        res.messages
+            .into_iter()
+            .filter_map(|msg| match msg.msg {
+                CosmosMsg::Ibc(IbcMsg::SendPacket { channel_id, data, .. }) => {
+                    assert_eq!(channel_id, CHANNEL);
+                    Some(from_json(data).unwrap())
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn pushed_pairs(packet: &PricePushPacket) -> Vec<String> {
+        let PricePushPacket::PriceUpdate { prices } = packet;
+        prices.iter().map(|price| price.pair_id.clone()).collect()
+    }
+
+    #[test]
+    fn channel_handshake() {
+        let (mut deps, _) = setup();
+        let open = mock_ibc_channel_open_init(CHANNEL, IBC_ORDERING, IBC_VERSION);
+        entry::ibc_channel_open(deps.as_mut(), mock_env(), open).unwrap();
+
+        let open = mock_ibc_channel_open_init(CHANNEL, IBC_ORDERING, "ics20-1");
+        let err = entry::ibc_channel_open(deps.as_mut(), mock_env(), open).unwrap_err();
+        assert_eq!(err, ContractError::InvalidIbcVersion { version: "ics20-1".to_string() });
+
+        let open = mock_ibc_channel_open_init(CHANNEL, IbcOrder::Ordered, IBC_VERSION);
+        let err = entry::ibc_channel_open(deps.as_mut(), mock_env(), open).unwrap_err();
+        assert_eq!(err, ContractError::OnlyUnorderedChannel {});
+    }
+
+    #[test]
+    fn get_prices_packet() {
+        let (mut deps, _) = setup();
+        let ack = receive(&mut deps, mock_env(), &PriceRequestPacket::GetPrices { pair_ids: pairs(&["btc/usd", "ETH/USD", "BTC/USD"]) });
+        let PriceAck::Result(data) = ack else { panic!("unexpected ack {:?}", ack) };
+        let res: GetPricesResponse = from_json(data).unwrap();
+        assert_eq!(res.prices.len(), 2);
+
+        // errors are acknowledged
+        let ack = receive(&mut deps, mock_env(), &PriceRequestPacket::GetPrices { pair_ids: pairs(&["SOL/USD"]) });
+        assert!(matches!(ack, PriceAck::Error(_)));
+        let msg = mock_ibc_packet_recv(CHANNEL, &"garbage").unwrap();
+        let res = entry::ibc_packet_receive(deps.as_mut(), mock_env(), msg).unwrap();
+        assert!(matches!(from_json(res.acknowledgement).unwrap(), PriceAck::Error(_)));
+    }
+
+    #[test]
+    fn get_prices_packet_events() {
+        let (mut deps, _) = setup();
+        let packet = PriceRequestPacket::GetPrices { pair_ids: pairs(&["BTC/USD", "ETH/USD"]) };
+        let msg = mock_ibc_packet_recv(CHANNEL, &packet).unwrap();
+        let res = entry::ibc_packet_receive(deps.as_mut(), mock_env(), msg.clone()).unwrap();
+        let served: Vec<&str> = res.events.iter().filter(|event| event.ty == "slinky_price").map(|event| event.attributes[1].value.as_str()).collect();
+        assert_eq!(served, vec!["BTC/USD", "ETH/USD"]);
+        assert_eq!(res.events[0].attributes[0].value, "ibc_packet_receive");
+
+        // a tripped circuit breaker is acknowledged as an error and reported as an event
+        let bounds = crate::bounds::PairBounds { min_price: None, max_price: Some(SignedDecimal256::percent(5_000)), max_deviation_bps: None, reference_price: None };
+        let msg_bounds = ExecuteMsg::SetPairBounds { pair: "BTC/USD".parse().unwrap(), bounds: Some(bounds) };
+        entry::execute(deps.as_mut(), mock_env(), mock_info(ADMIN, &[]), msg_bounds).unwrap();
+        let res = entry::ibc_packet_receive(deps.as_mut(), mock_env(), msg).unwrap();
+        assert!(matches!(from_json(&res.acknowledgement).unwrap(), PriceAck::Error(_)));
+        assert_eq!(res.events.len(), 1);
+        assert_eq!(res.events[0].ty, "slinky_price_rejected");
+        assert_eq!(res.events[0].attributes[1].value, "BTC/USD");
+        assert_eq!(res.events[0].attributes[2].value, "100 is above max_price 50");
+    }
+
+    #[test]
+    fn subscribe_pushes_on_poke() {
+        let (mut deps, _) = setup();
+        let ack = receive(&mut deps, mock_env(), &PriceRequestPacket::Subscribe { pair_ids: pairs(&["btc/usd", "BTC/USD"]), min_interval: 60 });
+        assert_eq!(ack, PriceAck::Result(Binary::default()));
+
+        let mut env = mock_env();
+        let packets = poke(&mut deps, env.clone());
+        assert_eq!(packets.len(), 1);
+        assert_eq!(pushed_pairs(&packets[0]), vec!["BTC/USD"]);
+
+        env.block.time = env.block.time.plus_seconds(59);
+        assert!(poke(&mut deps, env.clone()).is_empty());
+        env.block.time = env.block.time.plus_seconds(1);
+        assert_eq!(poke(&mut deps, env.clone()).len(), 1);
+
+        // unsubscribing and closing the channel stop the pushes
+        receive(&mut deps, mock_env(), &PriceRequestPacket::Unsubscribe {});
+        env.block.time = env.block.time.plus_seconds(60);
+        assert!(poke(&mut deps, env.clone()).is_empty());
+
+        receive(&mut deps, mock_env(), &PriceRequestPacket::Subscribe { pair_ids: pairs(&["BTC/USD"]), min_interval: 0 });
+        let close = mock_ibc_channel_close_confirm(CHANNEL, IBC_ORDERING, IBC_VERSION);
+        entry::ibc_channel_close(deps.as_mut(), mock_env(), close).unwrap();
+        assert!(poke(&mut deps, env).is_empty());
+    }
+
+    #[test]
+    fn subscribe_rejects_unknown_pairs() {
+        let (mut deps, _) = setup();
+        let ack = receive(&mut deps, mock_env(), &PriceRequestPacket::Subscribe { pair_ids: pairs(&["SOL/USD"]), min_interval: 0 });
+        assert!(matches!(ack, PriceAck::Error(_)));
+        let ack = receive(&mut deps, mock_env(), &PriceRequestPacket::Subscribe { pair_ids: vec![], min_interval: 0 });
+        assert_eq!(ack, PriceAck::Error(ContractError::EmptyPairs {}.to_string()));
+        assert!(poke(&mut deps, mock_env()).is_empty());
+    }
+
+    #[test]
+    fn push_skips_failing_pairs_and_saturates() {
+        let (mut deps, slinky) = setup();
+        receive(&mut deps, mock_env(), &PriceRequestPacket::Subscribe { pair_ids: pairs(&["BTC/USD", "ETH/USD"]), min_interval: u64::MAX });
+
+        slinky.remove_pair("ETH/USD");
+        let packets = poke(&mut deps, mock_env());
+        assert_eq!(pushed_pairs(&packets[0]), vec!["BTC/USD"]);
+
+        // min_interval never elapses instead of overflowing
+        let mut env = mock_env();
+        env.block.time = env.block.time.plus_seconds(1_000_000);
+        assert!(poke(&mut deps, env).is_empty());
+    }
+
+    #[test]
+    fn admin_removes_channel_subscriptions() {
+        let (mut deps, _) = setup();
+        receive(&mut deps, mock_env(), &PriceRequestPacket::Subscribe { pair_ids: pairs(&["BTC/USD"]), min_interval: 0 });
+
+        let query = QueryMsg::GetChannelSubscriptions { start_after: None, limit: None };
+        let res: ChannelSubscriptionsResponse = from_json(entry::query(deps.as_ref(), mock_env(), query.clone()).unwrap()).unwrap();
+        assert_eq!(res.subscriptions[0].channel_id, CHANNEL);
+        assert_eq!(res.subscriptions[0].subscription.pair_ids, vec!["BTC/USD"]);
+
+        let msg = ExecuteMsg::RemoveChannelSubscription { channel_id: CHANNEL.to_string() };
+        let err = entry::execute(deps.as_mut(), mock_env(), mock_info(KEEPER, &[]), msg.clone()).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+        entry::execute(deps.as_mut(), mock_env(), mock_info(ADMIN, &[]), msg.clone()).unwrap();
+        assert!(entry::execute(deps.as_mut(), mock_env(), mock_info(ADMIN, &[]), msg).is_err());
+
+        let res: ChannelSubscriptionsResponse = from_json(entry::query(deps.as_ref(), mock_env(), query).unwrap()).unwrap();
+        assert!(res.subscriptions.is_empty());
+        assert!(poke(&mut deps, mock_env()).is_empty());
+    }
+}
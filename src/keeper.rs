@@ -0,0 +1,287 @@
+use cosmwasm_std::{Addr, BankMsg, Coin, Deps, DepsMut, Env, MessageInfo, Order, Response, StdResult, Storage};
+use cw_storage_plus::Bound;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::error::ContractError;
+use crate::query::GetPriceResponse;
+use crate::state::Contract;
+
+const DEFAULT_LIMIT: u32 = 10;
+const MAX_LIMIT: u32 = 30;
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug, Default)]
+pub struct KeeperStats {
+    /// state-writing oracle calls made by the keeper
+    pub updates: u64,
+    /// calls in which at least one slinky nonce advanced
+    pub useful_updates: u64,
+    pub rewards_paid: Vec<Coin>,
+}
+
+/// Outcome of crediting a keeper for recording prices.
+pub struct KeeperUpdate {
+    /// whether a tracked slinky nonce advanced
+    pub useful: bool,
+    pub payout: Option<BankMsg>,
+}
+
+impl<'a> Contract<'a> {
+    // authorized by the caller, shared by execute and sudo
+    pub(crate) fn set_keeper_reward(
+        &self,
+        deps: DepsMut,
+        _env: Env,
+        reward: Option<Coin>,
+    ) -> Result<Response, ContractError> {
+        match reward.as_ref() {
+            Some(reward) => self.keeper_reward.save(deps.storage, reward)?,
+            None => self.keeper_reward.remove(deps.storage),
+        }
+
+        Ok(Response::new()
+            .add_attribute("action", "set_keeper_reward")
+            .add_attribute("reward", reward.map_or_else(|| "none".to_string(), |reward| reward.to_string())))
+    }
+
+    pub(crate) fn fund_reward_pool(
+        &self,
+        deps: DepsMut,
+        _env: Env,
+        info: MessageInfo,
+    ) -> Result<Response, ContractError> {
+        if info.funds.is_empty() {
+            return Err(ContractError::NoFunds {});
+        }
+        for coin in info.funds.iter() {
+            self.reward_pool.update(deps.storage, &coin.denom, |balance| -> StdResult<_> {
+                Ok(balance.unwrap_or_default().checked_add(coin.amount)?)
+            })?;
+        }
+
+        Ok(Response::new()
+            .add_attribute("action", "fund_reward_pool")
+            .add_attribute("sender", info.sender)
+            .add_attribute("funds", info.funds.iter().map(|coin| coin.to_string()).collect::<Vec<_>>().join(",")))
+    }
+
+    pub(crate) fn withdraw_reward_pool(
+        &self,
+        deps: DepsMut,
+        _env: Env,
+        info: MessageInfo,
+        amount: Coin,
+    ) -> Result<Response, ContractError> {
+        self.assert_admin(&deps, &info)?;
+        self.reward_pool.update(deps.storage, &amount.denom, |balance| -> StdResult<_> {
+            Ok(balance.unwrap_or_default().checked_sub(amount.amount)?)
+        })?;
+
+        Ok(Response::new()
+            .add_attribute("action", "withdraw_reward_pool")
+            .add_attribute("amount", amount.to_string())
+            .add_message(BankMsg::Send { to_address: info.sender.to_string(), amount: vec![amount] }))
+    }
+
+    // records the latest nonces of the given pairs and returns whether the nonce of an
+    // already tracked pair advanced, a pair seen for the first time only starts being tracked
+    pub(crate) fn advance_nonces(&self, storage: &mut dyn Storage, pair_ids: &[String], prices: &[GetPriceResponse]) -> StdResult<bool> {
+        let mut advanced = false;
+        for (pair_id, price) in pair_ids.iter().zip(prices.iter()) {
+            match self.last_nonces.may_load(storage, pair_id)? {
+                Some(last_nonce) if price.nonce <= last_nonce => {}
+                last_nonce => {
+                    self.last_nonces.save(storage, pair_id, &price.nonce)?;
+                    advanced |= last_nonce.is_some();
+                }
+            }
+        }
+        Ok(advanced)
+    }
+
+    // counts a keeper's contribution and pays the reward out of the pool for useful updates,
+    // as long as the pool can afford it
+    pub(crate) fn reward_keeper(&self, storage: &mut dyn Storage, keeper: &Addr, useful: bool) -> StdResult<Option<BankMsg>> {
+        let mut stats = self.keepers.may_load(storage, keeper)?.unwrap_or_default();
+        stats.updates += 1;
+
+        let mut payout = None;
+        if useful {
+            stats.useful_updates += 1;
+
+            if let Some(reward) = self.keeper_reward.may_load(storage)? {
+                let balance = self.reward_pool.may_load(storage, &reward.denom)?.unwrap_or_default();
+                if !reward.amount.is_zero() && balance >= reward.amount {
+                    self.reward_pool.save(storage, &reward.denom, &(balance - reward.amount))?;
+                    add_coin(&mut stats.rewards_paid, &reward);
+                    payout = Some(BankMsg::Send { to_address: keeper.to_string(), amount: vec![reward] });
+                }
+            }
+        }
+
+        self.keepers.save(storage, keeper, &stats)?;
+        Ok(payout)
+    }
+
+    pub(crate) fn get_keeper(&self, deps: Deps, _env: Env, address: String) -> StdResult<KeeperStats> {
+        let address = deps.api.addr_validate(&address)?;
+        Ok(self.keepers.may_load(deps.storage, &address)?.unwrap_or_default())
+    }
+
+    pub(crate) fn get_keepers(&self, deps: Deps, _env: Env, start_after: Option<String>, limit: Option<u32>) -> StdResult<KeepersResponse> {
+        let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+        let start_after = start_after.map(|address| deps.api.addr_validate(&address)).transpose()?;
+        let keepers = self.keepers
+            .range(deps.storage, start_after.as_ref().map(Bound::exclusive), None, Order::Ascending)
+            .take(limit)
+            .map(|item| item.map(|(address, stats)| KeeperResponse { address, stats }))
+            .collect::<StdResult<Vec<_>>>()?;
+
+        Ok(KeepersResponse { keepers })
+    }
+
+    pub(crate) fn get_reward_pool(&self, deps: Deps, _env: Env) -> StdResult<RewardPoolResponse> {
+        let reward = self.keeper_reward.may_load(deps.storage)?;
+        let pool = self.reward_pool
+            .range(deps.storage, None, None, Order::Ascending)
+            .map(|item| item.map(|(denom, amount)| Coin { denom, amount }))
+            .collect::<StdResult<Vec<_>>>()?;
+
+        Ok(RewardPoolResponse { reward, pool })
+    }
+}
+
+fn add_coin(coins: &mut Vec<Coin>, coin: &Coin) {
+    match coins.iter_mut().find(|c| c.denom == coin.denom) {
+        Some(c) => c.amount += coin.amount,
+        None => coins.push(coin.clone()),
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct KeeperResponse {
+    pub address: Addr,
+    pub stats: KeeperStats,
+}
+
+#[derive(Clone, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct KeepersResponse {
+    pub keepers: Vec<KeeperResponse>,
+}
+
+#[derive(Clone, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct RewardPoolResponse {
+    /// paid per useful update, none if rewards are disabled
+    pub reward: Option<Coin>,
+    pub pool: Vec<Coin>,
+}
+
+#[cfg(test)]
+mod tests {
+    use cosmwasm_std::coin;
+
+    use super::*;
+    use crate::currency_pair::CurrencyPair;
+    use crate::msgs::{ExecuteMsg, QueryMsg};
+    use crate::testing::{attribute, mock_subscriber, Suite, ADMIN, DENOM, KEEPER, USER};
+
+    fn setup(reward: u128, pool: u128) -> Suite {
+        let mut suite = Suite::new();
+        suite.set_price("BTC/USD", 100, 0);
+        suite.execute(ADMIN, &ExecuteMsg::SetKeeperReward { reward: Some(coin(reward, DENOM)) }, &[]).unwrap();
+        suite.execute(ADMIN, &ExecuteMsg::FundRewardPool {}, &[coin(pool, DENOM)]).unwrap();
+        suite
+    }
+
+    fn quote_and_record(suite: &mut Suite) -> String {
+        let msg = ExecuteMsg::QuoteAndRecord { pairs: vec![CurrencyPair::new("BTC", "USD").unwrap()] };
+        attribute(&suite.execute(KEEPER, &msg, &[]).unwrap(), "useful")
+    }
+
+    fn stats(suite: &Suite) -> KeeperStats {
+        suite.query(&QueryMsg::GetKeeper { address: KEEPER.to_string() }).unwrap()
+    }
+
+    #[test]
+    fn only_advanced_nonces_of_tracked_pairs_are_useful() {
+        let mut suite = setup(10, 100);
+        let balance = suite.balance(KEEPER);
+
+        // the first recording of a pair only starts tracking it
+        assert_eq!(quote_and_record(&mut suite), "false");
+        // nothing changed since
+        assert_eq!(quote_and_record(&mut suite), "false");
+        assert_eq!(suite.balance(KEEPER), balance);
+
+        suite.set_price("BTC/USD", 101, 0);
+        assert_eq!(quote_and_record(&mut suite), "true");
+        assert_eq!(suite.balance(KEEPER), balance + 10);
+
+        let stats = stats(&suite);
+        assert_eq!((stats.updates, stats.useful_updates), (3, 1));
+        assert_eq!(stats.rewards_paid, vec![coin(10, DENOM)]);
+        let pool: RewardPoolResponse = suite.query(&QueryMsg::GetRewardPool {}).unwrap();
+        assert_eq!(pool, RewardPoolResponse { reward: Some(coin(10, DENOM)), pool: vec![coin(90, DENOM)] });
+    }
+
+    #[test]
+    fn every_recording_execute_credits_the_keeper() {
+        let mut suite = setup(10, 100);
+        let subscriber = mock_subscriber(&mut suite, false);
+        let msg = ExecuteMsg::Subscribe {
+            pairs: vec![CurrencyPair::new("BTC", "USD").unwrap()],
+            contract: subscriber.to_string(),
+            min_interval: 0,
+            deviation_bps: 0,
+        };
+        suite.execute(USER, &msg, &[]).unwrap();
+        quote_and_record(&mut suite);
+
+        suite.set_price("BTC/USD", 101, 0);
+        let res = suite.execute(KEEPER, &ExecuteMsg::Poke { limit: None }, &[]).unwrap();
+        assert_eq!(attribute(&res, "useful"), "true");
+        // the nonce was already seen by the poke
+        assert_eq!(quote_and_record(&mut suite), "false");
+
+        suite.set_price("BTC/USD", 102, 0);
+        let msg = ExecuteMsg::RegisterSettlement {
+            pair: CurrencyPair::new("BTC", "USD").unwrap(),
+            settle_at: suite.app.block_info().time.plus_seconds(5),
+            max_delay: None,
+        };
+        suite.execute(USER, &msg, &[]).unwrap();
+        suite.advance(5);
+        suite.set_price("BTC/USD", 103, 0);
+        let res = suite.execute(KEEPER, &ExecuteMsg::FinalizeSettlement { id: 1 }, &[]).unwrap();
+        assert_eq!(attribute(&res, "useful"), "true");
+
+        let stats = stats(&suite);
+        assert_eq!((stats.updates, stats.useful_updates), (4, 2));
+        assert_eq!(stats.rewards_paid, vec![coin(20, DENOM)]);
+    }
+
+    #[test]
+    fn rewards_stop_when_the_pool_runs_dry() {
+        let mut suite = setup(10, 15);
+        quote_and_record(&mut suite);
+        let balance = suite.balance(KEEPER);
+
+        suite.set_price("BTC/USD", 101, 0);
+        quote_and_record(&mut suite);
+        suite.set_price("BTC/USD", 102, 0);
+        assert_eq!(quote_and_record(&mut suite), "true");
+        assert_eq!(suite.balance(KEEPER), balance + 10);
+        assert_eq!(stats(&suite).useful_updates, 2);
+
+        // disabling rewards keeps counting
+        let res = suite.execute(ADMIN, &ExecuteMsg::SetKeeperReward { reward: None }, &[]).unwrap();
+        assert_eq!(attribute(&res, "reward"), "none");
+
+        // the admin withdraws what is left
+        assert!(suite.execute(USER, &ExecuteMsg::WithdrawRewardPool { amount: coin(5, DENOM) }, &[]).is_err());
+        assert!(suite.execute(ADMIN, &ExecuteMsg::WithdrawRewardPool { amount: coin(6, DENOM) }, &[]).is_err());
+        suite.execute(ADMIN, &ExecuteMsg::WithdrawRewardPool { amount: coin(5, DENOM) }, &[]).unwrap();
+        let pool: RewardPoolResponse = suite.query(&QueryMsg::GetRewardPool {}).unwrap();
+        assert_eq!(pool, RewardPoolResponse { reward: None, pool: vec![coin(0, DENOM)] });
+    }
+}
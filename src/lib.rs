@@ -1,12 +1,37 @@
+mod bounds;
+mod candle;
+mod compat;
+mod cumulative;
+mod currency_pair;
+mod deserialize;
+mod error;
+mod events;
 mod execute;
+mod ibc;
+mod keeper;
+mod math;
+mod migrate;
+mod order;
+mod oracle_source;
+mod pause;
+mod receipt;
+mod recording;
+mod settlement;
 mod state;
+mod subscription;
 mod msgs;
 mod query;
+#[allow(renamed_and_removed_lints)]
 mod slinky_oracle;
 mod timestamp;
+mod twap;
+#[cfg(test)]
+mod testing;
 
-use crate::msgs::{InstantiateMsg, ExecuteMsg, 
-    // QueryMsg, MigrateMsg
+pub use crate::currency_pair::CurrencyPair;
+use crate::error::ContractError;
+use crate::msgs::{InstantiateMsg, ExecuteMsg, SudoMsg, MigrateMsg,
+    // QueryMsg
 };
 use crate::state::Contract;
 
@@ -16,8 +41,12 @@ pub mod entry {
 
 use super::*;
 
-  use cosmwasm_std::{entry_point, Binary, Deps, Empty};
-  use cosmwasm_std::{DepsMut, Env, MessageInfo, Response, StdResult};
+  use cosmwasm_std::{entry_point, Binary, Deps, Reply};
+  use cosmwasm_std::{DepsMut, Env, MessageInfo, Response};
+  use cosmwasm_std::{
+    IbcBasicResponse, IbcChannelCloseMsg, IbcChannelConnectMsg, IbcChannelOpenMsg, IbcChannelOpenResponse,
+    IbcPacketAckMsg, IbcPacketReceiveMsg, IbcPacketTimeoutMsg, IbcReceiveResponse,
+  };
 
 
   #[entry_point]
@@ -26,7 +55,7 @@ use super::*;
     env: Env,
     info: MessageInfo,
     msg: InstantiateMsg,
-  ) -> StdResult<Response> {
+  ) -> Result<Response, ContractError> {
     let tract = Contract::default();
     tract.instantiate(deps, env, info, msg)
   }
@@ -37,19 +66,68 @@ use super::*;
     env: Env,
     info: MessageInfo,
     msg: ExecuteMsg,
-  ) -> StdResult<Response> {
+  ) -> Result<Response, ContractError> {
     let tract = Contract::default();
     tract.execute(deps, env, info, msg)
   }
 
   #[entry_point]
-  pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
+  pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> Result<Binary, ContractError> {
     let tract = Contract::default();
       tract.query(deps, env, msg)
   }
 
   #[entry_point]
-  pub fn migrate(_deps: DepsMut, _env: Env, _msg: Empty) -> StdResult<Response> {
-    Ok(Response::new())
+  pub fn sudo(deps: DepsMut, env: Env, msg: SudoMsg) -> Result<Response, ContractError> {
+    let tract = Contract::default();
+    tract.sudo(deps, env, msg)
+  }
+
+  #[entry_point]
+  pub fn reply(deps: DepsMut, env: Env, msg: Reply) -> Result<Response, ContractError> {
+    let tract = Contract::default();
+    tract.reply(deps, env, msg)
+  }
+
+  #[entry_point]
+  pub fn ibc_channel_open(deps: DepsMut, env: Env, msg: IbcChannelOpenMsg) -> Result<IbcChannelOpenResponse, ContractError> {
+    let tract = Contract::default();
+    tract.ibc_channel_open(deps, env, msg)
+  }
+
+  #[entry_point]
+  pub fn ibc_channel_connect(deps: DepsMut, env: Env, msg: IbcChannelConnectMsg) -> Result<IbcBasicResponse, ContractError> {
+    let tract = Contract::default();
+    tract.ibc_channel_connect(deps, env, msg)
+  }
+
+  #[entry_point]
+  pub fn ibc_channel_close(deps: DepsMut, env: Env, msg: IbcChannelCloseMsg) -> Result<IbcBasicResponse, ContractError> {
+    let tract = Contract::default();
+    tract.ibc_channel_close(deps, env, msg)
+  }
+
+  #[entry_point]
+  pub fn ibc_packet_receive(deps: DepsMut, env: Env, msg: IbcPacketReceiveMsg) -> Result<IbcReceiveResponse, ContractError> {
+    let tract = Contract::default();
+    tract.ibc_packet_receive(deps, env, msg)
+  }
+
+  #[entry_point]
+  pub fn ibc_packet_ack(deps: DepsMut, env: Env, msg: IbcPacketAckMsg) -> Result<IbcBasicResponse, ContractError> {
+    let tract = Contract::default();
+    tract.ibc_packet_ack(deps, env, msg)
+  }
+
+  #[entry_point]
+  pub fn ibc_packet_timeout(deps: DepsMut, env: Env, msg: IbcPacketTimeoutMsg) -> Result<IbcBasicResponse, ContractError> {
+    let tract = Contract::default();
+    tract.ibc_packet_timeout(deps, env, msg)
+  }
+
+  #[entry_point]
+  pub fn migrate(deps: DepsMut, env: Env, msg: MigrateMsg) -> Result<Response, ContractError> {
+    let tract = Contract::default();
+    tract.migrate(deps, env, msg)
   }
 }
@@ -0,0 +1,183 @@
+use cosmwasm_std::{Decimal256, Int256, Int64, SignedDecimal256, StdError, StdResult, Uint128, Uint256, Uint512};
+
+use crate::msgs::RoundingMode;
+
+// converts `amount` base units of a token into base units of another token
+// using both tokens' prices against a common quote
+//
+// result = amount * from_price * 10^(to_price_decimals + to_decimals)
+//          / (to_price * 10^(from_decimals + from_price_decimals))
+pub fn convert_amount(
+    amount: Uint128,
+    from_decimals: u32,
+    (from_price, from_price_decimals): (Uint256, u64),
+    to_decimals: u32,
+    (to_price, to_price_decimals): (Uint256, u64),
+    rounding: RoundingMode,
+) -> StdResult<Uint128> {
+    if to_price.is_zero() {
+        return Err(StdError::generic_err("cannot convert into a token with zero price"));
+    }
+
+    let numerator = Uint512::from(amount)
+        .checked_mul(Uint512::from(from_price))?
+        .checked_mul(pow10(to_price_decimals + to_decimals as u64)?)?;
+    let denominator = Uint512::from(to_price)
+        .checked_mul(pow10(from_decimals as u64 + from_price_decimals)?)?;
+
+    let result = div_with_rounding(numerator, denominator, rounding)?;
+    Uint128::try_from(result).map_err(|_| StdError::generic_err("converted amount overflows Uint128"))
+}
+
+// value of `amount` base units in whole units of the price's quote
+pub fn value_of(amount: Uint128, decimals: u32, (price, price_decimals): (Uint256, u64)) -> StdResult<Decimal256> {
+    let atomics = Uint256::from(amount).checked_mul(price)?;
+    let decimal_places = u32::try_from(decimals as u64 + price_decimals)
+        .map_err(|_| StdError::generic_err("decimals out of range"))?;
+    Decimal256::from_atomics(atomics, decimal_places)
+        .map_err(|_| StdError::generic_err("value overflows Decimal256"))
+}
+
+pub fn price_to_decimal(price: Int256, decimals: u64) -> StdResult<SignedDecimal256> {
+    let decimal_places = u32::try_from(decimals).map_err(|_| StdError::generic_err("decimals out of range"))?;
+    SignedDecimal256::from_atomics(price, decimal_places)
+        .map_err(|_| StdError::generic_err("price overflows SignedDecimal256"))
+}
+
+// `value` in base units of `decimals` decimals, truncated towards zero
+pub fn decimal_to_price(value: SignedDecimal256, decimals: u64) -> StdResult<Int256> {
+    let places = SignedDecimal256::DECIMAL_PLACES as u64;
+    let scale = |exp: u64| -> StdResult<Int256> { Ok(Int256::try_from(Uint256::try_from(pow10(exp)?)?)?) };
+    if decimals <= places {
+        Ok(value.atomics().checked_div(scale(places - decimals)?).map_err(|err| StdError::generic_err(err.to_string()))?)
+    } else {
+        Ok(value.atomics().checked_mul(scale(decimals - places)?)?)
+    }
+}
+
+// prices of spreads, basis or funding rates can be negative, token prices cannot
+pub fn unsigned_price(price: Int256) -> StdResult<Uint256> {
+    Uint256::try_from(price).map_err(|_| StdError::generic_err(format!("negative price {} is not supported here", price)))
+}
+
+// the halfway point of the middle values, fails rather than overflowing on values far apart
+pub fn median(mut values: Vec<SignedDecimal256>) -> StdResult<Option<SignedDecimal256>> {
+    if values.is_empty() {
+        return Ok(None);
+    }
+
+    values.sort();
+    let mid = values.len() / 2;
+    if values.len() % 2 == 1 {
+        Ok(Some(values[mid]))
+    } else {
+        let (low, high) = (values[mid - 1], values[mid]);
+        let half = high.checked_sub(low)?.checked_mul(SignedDecimal256::percent(50))?;
+        Ok(Some(low.checked_add(half)?))
+    }
+}
+
+// |value - reference| / |reference| in basis points
+pub fn deviation_bps(value: SignedDecimal256, reference: SignedDecimal256) -> StdResult<Decimal256> {
+    let diff = value.abs_diff(reference);
+    diff.checked_mul(Decimal256::from_ratio(10_000u32, 1u32))?
+        .checked_div(reference.abs_diff(SignedDecimal256::zero()))
+        .map_err(|err| StdError::generic_err(err.to_string()))
+}
+
+pub fn div_with_rounding(numerator: Uint512, denominator: Uint512, rounding: RoundingMode) -> StdResult<Uint512> {
+    let quotient = numerator.checked_div(denominator)?;
+    let remainder = numerator.checked_rem(denominator)?;
+
+    let round_up = match rounding {
+        RoundingMode::Floor => false,
+        RoundingMode::Ceil => !remainder.is_zero(),
+        RoundingMode::HalfUp => remainder.checked_mul(Uint512::from(2u8))? >= denominator,
+    };
+
+    if round_up {
+        Ok(quotient.checked_add(Uint512::one())?)
+    } else {
+        Ok(quotient)
+    }
+}
+
+const LN_2: &str = "0.693147180559945309";
+
+pub fn ln_2() -> SignedDecimal256 {
+    LN_2.parse().unwrap()
+}
+
+// natural logarithm, x = m * 2^k with m in [1, 2) and ln(m) = 2 * atanh((m - 1) / (m + 1))
+pub fn ln(x: Decimal256) -> StdResult<SignedDecimal256> {
+    if x.is_zero() {
+        return Err(StdError::generic_err("logarithm of zero"));
+    }
+    let two = Decimal256::from_ratio(2u8, 1u8);
+    let mut m = x;
+    let mut k: i64 = 0;
+    while m >= two {
+        m = checked_div(m, two)?;
+        k += 1;
+    }
+    while m < Decimal256::one() {
+        m = m.checked_mul(two)?;
+        k -= 1;
+    }
+
+    let z = checked_div(m - Decimal256::one(), m + Decimal256::one())?;
+    let z2 = z.checked_mul(z)?;
+    let mut power = z;
+    let mut sum = Decimal256::zero();
+    let mut n = 0u64;
+    loop {
+        let term = checked_div(power, Decimal256::from_ratio(2 * n + 1, 1u64))?;
+        if term.is_zero() {
+            break;
+        }
+        sum = sum.checked_add(term)?;
+        power = power.checked_mul(z2)?;
+        n += 1;
+    }
+
+    let ln_m = SignedDecimal256::try_from(sum.checked_mul(two)?)
+        .map_err(|_| StdError::generic_err("logarithm overflows SignedDecimal256"))?;
+    Ok(ln_m.checked_add(ln_2().checked_mul(SignedDecimal256::from_ratio(k, 1))?)?)
+}
+
+// e^y = 2^k * e^r with r in [0, ln 2), e^r by its taylor series
+pub fn exp(y: SignedDecimal256) -> StdResult<Decimal256> {
+    let k = y.checked_div(ln_2())
+        .map_err(|err| StdError::generic_err(err.to_string()))?
+        .to_int_floor();
+    let k = Int64::try_from(k).map_err(|_| StdError::generic_err("exponent out of range"))?.i64();
+    let r = y.checked_sub(ln_2().checked_mul(SignedDecimal256::from_ratio(k, 1))?)?;
+    let r = Decimal256::try_from(r).map_err(|_| StdError::generic_err("exponent out of range"))?;
+
+    let mut term = Decimal256::one();
+    let mut sum = Decimal256::one();
+    let mut n = 1u64;
+    loop {
+        term = checked_div(term.checked_mul(r)?, Decimal256::from_ratio(n, 1u64))?;
+        if term.is_zero() {
+            break;
+        }
+        sum = sum.checked_add(term)?;
+        n += 1;
+    }
+
+    let two = Decimal256::from_ratio(2u8, 1u8);
+    for _ in 0..k.unsigned_abs() {
+        sum = if k > 0 { sum.checked_mul(two)? } else { checked_div(sum, two)? };
+    }
+    Ok(sum)
+}
+
+fn checked_div(numerator: Decimal256, denominator: Decimal256) -> StdResult<Decimal256> {
+    numerator.checked_div(denominator).map_err(|err| StdError::generic_err(err.to_string()))
+}
+
+pub fn pow10(exp: u64) -> StdResult<Uint512> {
+    let exp = u32::try_from(exp).map_err(|_| StdError::generic_err("decimals out of range"))?;
+    Ok(Uint512::from(10u8).checked_pow(exp)?)
+}
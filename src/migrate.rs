@@ -0,0 +1,141 @@
+use cosmwasm_std::{DepsMut, Env, Response};
+use cw2::{set_contract_version, CONTRACT};
+use semver::Version;
+
+use crate::currency_pair::normalize_ticker;
+use crate::error::ContractError;
+use crate::msgs::MigrateMsg;
+use crate::state::{Config, Contract};
+
+pub const CONTRACT_NAME: &str = concat!("crates.io:", env!("CARGO_PKG_NAME"));
+pub const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+// version of deployments made before cw2 version tracking
+const UNVERSIONED: &str = "0.0.0";
+
+impl<'a> Contract<'a> {
+    pub fn migrate(
+        &self,
+        mut deps: DepsMut,
+        _env: Env,
+        msg: MigrateMsg,
+    ) -> Result<Response, ContractError> {
+        let from = match CONTRACT.may_load(deps.storage)? {
+            Some(stored) => {
+                if stored.contract != CONTRACT_NAME {
+                    return Err(ContractError::InvalidContractName { name: stored.contract });
+                }
+                stored.version
+            }
+            None => UNVERSIONED.to_string(),
+        };
+
+        let from_version = parse_version(&from)?;
+        if from_version > parse_version(CONTRACT_VERSION)? {
+            return Err(ContractError::Downgrade { from, to: CONTRACT_VERSION.to_string() });
+        }
+
+        self.migrate_state(deps.branch(), &from_version, &msg)?;
+        set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+        Ok(Response::new()
+            .add_attribute("action", "migrate")
+            .add_attribute("from_version", from)
+            .add_attribute("to_version", CONTRACT_VERSION))
+    }
+
+    // storage layout changes, keyed on the version being migrated from
+    fn migrate_state(&self, deps: DepsMut, from: &Version, msg: &MigrateMsg) -> Result<(), ContractError> {
+        // unversioned deployments have no config
+        if *from == parse_version(UNVERSIONED)? && !self.config.exists(deps.storage) {
+            let admin = msg.admin.as_ref().ok_or(ContractError::MissingAdmin {})?;
+            self.config.save(deps.storage, &Config {
+                admin: deps.api.addr_validate(admin)?,
+                quote: normalize_ticker(msg.quote.as_deref().unwrap_or("USD"))?,
+            })?;
+            return Ok(());
+        }
+
+        let mut config = self.config.load(deps.storage)?;
+        if let Some(admin) = msg.admin.as_ref() {
+            config.admin = deps.api.addr_validate(admin)?;
+        }
+        if let Some(quote) = msg.quote.as_ref() {
+            config.quote = normalize_ticker(quote)?;
+        }
+        self.config.save(deps.storage, &config)?;
+
+        Ok(())
+    }
+}
+
+fn parse_version(version: &str) -> Result<Version, ContractError> {
+    Version::parse(version).map_err(|_| ContractError::InvalidVersion { version: version.to_string() })
+}
+
+#[cfg(test)]
+mod tests {
+    use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
+    use cosmwasm_std::Addr;
+    use cw2::{get_contract_version, ContractVersion};
+
+    use super::*;
+    use crate::entry;
+    use crate::msgs::InstantiateMsg;
+    use crate::testing::{ADMIN, USER};
+
+    fn migrate_msg(admin: Option<&str>, quote: Option<&str>) -> MigrateMsg {
+        MigrateMsg { admin: admin.map(ToString::to_string), quote: quote.map(ToString::to_string) }
+    }
+
+    #[test]
+    fn migrate_updates_config_and_version() {
+        let mut deps = mock_dependencies();
+        entry::instantiate(deps.as_mut(), mock_env(), mock_info(ADMIN, &[]), InstantiateMsg { admin: None, quote: None }).unwrap();
+        set_contract_version(deps.as_mut().storage, CONTRACT_NAME, "0.0.0").unwrap();
+
+        let res = entry::migrate(deps.as_mut(), mock_env(), migrate_msg(Some(USER), Some("EUR"))).unwrap();
+        assert_eq!(res.attributes[1].value, "0.0.0");
+        assert_eq!(res.attributes[2].value, CONTRACT_VERSION);
+        let config = Contract::default().config.load(deps.as_ref().storage).unwrap();
+        assert_eq!(config, Config { admin: Addr::unchecked(USER), quote: "EUR".to_string() });
+        assert_eq!(
+            get_contract_version(deps.as_ref().storage).unwrap(),
+            ContractVersion { contract: CONTRACT_NAME.to_string(), version: CONTRACT_VERSION.to_string() },
+        );
+
+        // migrating to the same version keeps the config
+        entry::migrate(deps.as_mut(), mock_env(), migrate_msg(None, None)).unwrap();
+        assert_eq!(Contract::default().config.load(deps.as_ref().storage).unwrap().quote, "EUR");
+    }
+
+    #[test]
+    fn migrate_refuses_downgrades_and_other_contracts() {
+        let mut deps = mock_dependencies();
+        entry::instantiate(deps.as_mut(), mock_env(), mock_info(ADMIN, &[]), InstantiateMsg { admin: None, quote: None }).unwrap();
+
+        set_contract_version(deps.as_mut().storage, CONTRACT_NAME, "99.0.0").unwrap();
+        let err = entry::migrate(deps.as_mut(), mock_env(), migrate_msg(None, None)).unwrap_err();
+        assert_eq!(err, ContractError::Downgrade { from: "99.0.0".to_string(), to: CONTRACT_VERSION.to_string() });
+
+        set_contract_version(deps.as_mut().storage, CONTRACT_NAME, "latest").unwrap();
+        let err = entry::migrate(deps.as_mut(), mock_env(), migrate_msg(None, None)).unwrap_err();
+        assert_eq!(err, ContractError::InvalidVersion { version: "latest".to_string() });
+
+        set_contract_version(deps.as_mut().storage, "crates.io:cw20-base", "0.1.0").unwrap();
+        let err = entry::migrate(deps.as_mut(), mock_env(), migrate_msg(None, None)).unwrap_err();
+        assert_eq!(err, ContractError::InvalidContractName { name: "crates.io:cw20-base".to_string() });
+    }
+
+    #[test]
+    fn migrate_unversioned_contract() {
+        let mut deps = mock_dependencies();
+        let err = entry::migrate(deps.as_mut(), mock_env(), migrate_msg(None, None)).unwrap_err();
+        assert_eq!(err, ContractError::MissingAdmin {});
+
+        entry::migrate(deps.as_mut(), mock_env(), migrate_msg(Some(ADMIN), None)).unwrap();
+        let config = Contract::default().config.load(deps.as_ref().storage).unwrap();
+        assert_eq!(config, Config { admin: Addr::unchecked(ADMIN), quote: "USD".to_string() });
+        assert_eq!(get_contract_version(deps.as_ref().storage).unwrap().version, CONTRACT_VERSION);
+    }
+}
@@ -1,25 +1,352 @@
+use cosmwasm_std::{Coin, SignedDecimal256, Timestamp};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
+use crate::bounds::PairBounds;
+use crate::currency_pair::CurrencyPair;
+use crate::oracle_source::OracleSourceConfig;
+use crate::order::{OrderAction, PriceCondition};
+use crate::twap::TwapMode;
+use crate::state::FreshnessThresholds;
+
 
 #[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
-pub struct InstantiateMsg {}
+pub struct InstantiateMsg {
+  /// defaults to the instantiator
+  pub admin: Option<String>,
+  /// defaults to "USD"
+  pub quote: Option<String>,
+}
 
 #[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
 #[serde(rename_all = "snake_case")]
 pub enum ExecuteMsg {
-  Foo {},
+  RegisterDenom {
+    denom: String,
+    symbol: String,
+    decimals: u32,
+  },
+  RemoveDenom {
+    denom: String,
+  },
+  SetOracleSources {
+    sources: Vec<OracleSourceConfig>,
+  },
+  RegisterPythFeed {
+    id: String,
+    base: String,
+    quote: String,
+    /// half-life in seconds of the ema reported as `ema_price`, 60 when not set
+    ema_half_life: Option<u64>,
+  },
+  RemovePythFeed {
+    id: String,
+  },
+  Subscribe {
+    /// duplicates are dropped
+    pairs: Vec<CurrencyPair>,
+    contract: String,
+    min_interval: u64,
+    deviation_bps: u64,
+  },
+  Unsubscribe {
+    id: u64,
+  },
+  /// drops the price subscription of an IBC channel
+  RemoveChannelSubscription {
+    channel_id: String,
+  },
+  /// serves the next `limit` subscriptions and channels, round robin
+  Poke {
+    limit: Option<u32>,
+  },
+  SetKeeperReward {
+    /// paid per useful update, none to disable rewards
+    reward: Option<Coin>,
+  },
+  /// circuit breaker of the pair, none removes it
+  SetPairBounds {
+    pair: CurrencyPair,
+    bounds: Option<PairBounds>,
+  },
+  FundRewardPool {},
+  WithdrawRewardPool {
+    amount: Coin,
+  },
+  SetGuardians {
+    guardians: Vec<String>,
+  },
+  /// admin or guardians
+  Pause {
+    target: PauseTarget,
+  },
+  /// admin only
+  Unpause {
+    target: PauseTarget,
+  },
+  SetFreshnessThresholds {
+    thresholds: FreshnessThresholds,
+  },
+  /// funds sent along are escrowed for `msgs`
+  CreateOrder {
+    pair: CurrencyPair,
+    condition: PriceCondition,
+    /// in whole quote units
+    threshold: SignedDecimal256,
+    /// seconds the twap must also meet the condition for, fires on the spot price when not set
+    twap_window: Option<u64>,
+    actions: Vec<OrderAction>,
+    expires_at: Timestamp,
+  },
+  /// owner only, refunds the escrow
+  CancelOrder {
+    id: u64,
+  },
+  /// permissionless
+  Trigger {
+    ids: Vec<u64>,
+  },
+  RegisterSettlement {
+    pair: CurrencyPair,
+    settle_at: Timestamp,
+    /// seconds after `settle_at` the settlement price may be from, 10 when not set, at most 60
+    max_delay: Option<u64>,
+  },
+  /// permissionless, records the current price, settling once a price at or after `settle_at` is available
+  FinalizeSettlement {
+    id: u64,
+  },
+  /// stores a receipt and returns the prices as response data
+  QuoteAndRecord {
+    /// duplicates are dropped
+    pairs: Vec<CurrencyPair>,
+  },
+  SetCandleIntervals {
+    /// seconds
+    intervals: Vec<u64>,
+  },
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct MigrateMsg {
+  /// required when migrating from a contract without config
+  pub admin: Option<String>,
+  pub quote: Option<String>,
+}
+
+/// Messages from chain governance or a chain module, which need no admin key.
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum SudoMsg {
+  SetConfig {
+    admin: Option<String>,
+    quote: Option<String>,
+  },
+  SetPairBounds {
+    pair: CurrencyPair,
+    bounds: Option<PairBounds>,
+  },
+  SetOracleSources {
+    sources: Vec<OracleSourceConfig>,
+  },
+  SetKeeperReward {
+    reward: Option<Coin>,
+  },
+  ForceUnpause {},
+  RemoveChannelSubscription {
+    channel_id: String,
+  },
+  SetFreshnessThresholds {
+    thresholds: FreshnessThresholds,
+  },
+  SetCandleIntervals {
+    intervals: Vec<u64>,
+  },
 }
 
 #[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
 #[serde(rename_all = "snake_case")]
 pub enum QueryMsg {
   GetPrice {
-    base: String,
-    quote: String,
+    #[serde(flatten)]
+    pair: CurrencyPair,
   },
   GetPrices {
-    pair_ids: Vec<String>
+    /// duplicates are dropped
+    pair_ids: Vec<CurrencyPair>
+  },
+  GetAllCurrencyPairs {
+    start_after: Option<CurrencyPair>,
+    /// every matching pair when not set
+    limit: Option<u32>,
+    base: Option<String>,
+    quote: Option<String>,
+    /// by {Base}/{Quote}, ascending when not set
+    order: Option<SortOrder>,
+  },
+  GetPricesLenient {
+    /// duplicates are dropped
+    pair_ids: Vec<CurrencyPair>
+  },
+  GetAllPrices {
+    quote_filter: Option<String>,
+    start_after: Option<CurrencyPair>,
+    limit: Option<u32>,
+  },
+  GetDenomMetadata {
+    denom: String,
+  },
+  ConvertAmount {
+    amount: Coin,
+    to_denom: String,
+    rounding: RoundingMode,
+  },
+  ValuePortfolio {
+    coins: Vec<Coin>,
+    quote: String,
+  },
+  GetOracleSources {},
+  GetPairBounds {
+    #[serde(flatten)]
+    pair: CurrencyPair,
+  },
+  GetAggregatedPrice {
+    #[serde(flatten)]
+    pair: CurrencyPair,
+    policy: AggregationPolicy,
+    /// sources whose price is older than this many seconds are unhealthy
+    max_age: Option<u64>,
+  },
+  LatestRoundData {
+    #[serde(flatten)]
+    pair: CurrencyPair,
+  },
+  GetRoundData {
+    #[serde(flatten)]
+    pair: CurrencyPair,
+    round_id: u64,
+  },
+  Decimals {
+    #[serde(flatten)]
+    pair: CurrencyPair,
+  },
+  Description {
+    #[serde(flatten)]
+    pair: CurrencyPair,
+  },
+  GetReferenceData {
+    base_symbol: String,
+    quote_symbol: String,
+  },
+  GetReferenceDataBulk {
+    base_symbols: Vec<String>,
+    quote_symbols: Vec<String>,
+  },
+  PriceFeed {
+    id: String,
+  },
+  GetSubscription {
+    id: u64,
+  },
+  GetSubscriptions {
+    start_after: Option<u64>,
+    limit: Option<u32>,
+  },
+  GetChannelSubscriptions {
+    start_after: Option<String>,
+    limit: Option<u32>,
+  },
+  GetKeeper {
+    address: String,
+  },
+  GetKeepers {
+    start_after: Option<String>,
+    limit: Option<u32>,
+  },
+  GetRewardPool {},
+  GetPauseState {},
+  GetGuardians {},
+  GetFreshnessThresholds {},
+  GetOrder {
+    id: u64,
+  },
+  GetOrders {
+    owner: Option<String>,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+  },
+  GetSettlement {
+    id: u64,
+  },
+  GetReceipt {
+    id: u64,
+  },
+  GetCumulative {
+    #[serde(flatten)]
+    pair: CurrencyPair,
+  },
+  GetTwap {
+    #[serde(flatten)]
+    pair: CurrencyPair,
+    /// seconds
+    window: u64,
+    mode: TwapMode,
+  },
+  GetEma {
+    #[serde(flatten)]
+    pair: CurrencyPair,
+    /// seconds
+    half_life: u64,
+  },
+  GetCandleIntervals {},
+  GetCandles {
+    #[serde(flatten)]
+    pair: CurrencyPair,
+    /// seconds, one of the candle intervals
+    interval: u64,
+    /// open time
+    start_after: Option<Timestamp>,
+    limit: Option<u32>,
+  },
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, JsonSchema, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum SortOrder {
+  Ascending,
+  Descending,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, JsonSchema, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum RoundingMode {
+  Floor,
+  Ceil,
+  HalfUp,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum PauseTarget {
+  All {},
+  Pair {
+    pair_id: CurrencyPair,
+  },
+  Query {
+    /// query message name, one of `QUERY_KINDS`, e.g. "get_price"
+    kind: String,
+  },
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum AggregationPolicy {
+  /// median of all healthy sources
+  Median {},
+  /// first healthy source in configured order
+  FirstHealthy {},
+  /// median of all healthy sources, failing if any of them deviates more than `max_deviation_bps` from it
+  MaxDeviation {
+    max_deviation_bps: u64,
   },
-  GetAllCurrencyPairs {},
 }
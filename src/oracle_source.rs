@@ -0,0 +1,176 @@
+use cosmwasm_std::{Deps, Env, QueryRequest, WasmQuery, to_json_binary};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::currency_pair::CurrencyPair;
+use crate::error::ContractError;
+use crate::msgs::QueryMsg;
+use crate::query::GetPriceResponse;
+use crate::state::Contract;
+
+/// A source of `{base}/{quote}` prices.
+pub trait OracleSource {
+    fn name(&self) -> String;
+
+    fn get_price(&self, deps: Deps, env: Env, pair: CurrencyPair) -> Result<GetPriceResponse, ContractError>;
+}
+
+/// Slinky x/oracle module, queried through stargate.
+pub struct SlinkySource<'c, 'a> {
+    pub contract: &'c Contract<'a>,
+}
+
+impl<'c, 'a> OracleSource for SlinkySource<'c, 'a> {
+    fn name(&self) -> String {
+        "slinky".to_string()
+    }
+
+    fn get_price(&self, deps: Deps, env: Env, pair: CurrencyPair) -> Result<GetPriceResponse, ContractError> {
+        self.contract.get_price(deps, env, pair)
+    }
+}
+
+/// CosmWasm price feed contract answering `{"get_price": {"base", "quote"}}` with a `GetPriceResponse`,
+/// e.g. another instance of this contract on top of a different oracle.
+pub struct PriceFeedContractSource {
+    pub contract_addr: String,
+}
+
+impl OracleSource for PriceFeedContractSource {
+    fn name(&self) -> String {
+        format!("contract:{}", self.contract_addr)
+    }
+
+    fn get_price(&self, deps: Deps, _env: Env, pair: CurrencyPair) -> Result<GetPriceResponse, ContractError> {
+        let request = QueryRequest::Wasm(WasmQuery::Smart {
+            contract_addr: self.contract_addr.clone(),
+            msg: to_json_binary(&QueryMsg::GetPrice { pair })?,
+        });
+        Ok(deps.querier.query(&request)?)
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum OracleSourceConfig {
+    Slinky {},
+    PriceFeedContract {
+        contract_addr: String,
+    },
+}
+
+impl OracleSourceConfig {
+    pub fn build<'c, 'a>(&self, contract: &'c Contract<'a>) -> Box<dyn OracleSource + 'c> {
+        match self {
+            OracleSourceConfig::Slinky {} => Box::new(SlinkySource { contract }),
+            OracleSourceConfig::PriceFeedContract { contract_addr } => Box::new(PriceFeedContractSource { contract_addr: contract_addr.clone() }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cosmwasm_std::{Int256, SignedDecimal256, StdResult};
+
+    use super::*;
+    use crate::msgs::{AggregationPolicy, ExecuteMsg};
+    use crate::query::{AggregatedPriceResponse, QuotePrice};
+    use crate::testing::{mock_feed, Suite, ADMIN};
+
+    fn feed(suite: &mut Suite, price: i128, age: u64) -> OracleSourceConfig {
+        feed_with_decimals(suite, Int256::from(price), 2, age)
+    }
+
+    fn feed_with_decimals(suite: &mut Suite, price: Int256, decimals: u64, age: u64) -> OracleSourceConfig {
+        let block = suite.app.block_info();
+        let price = GetPriceResponse {
+            price: QuotePrice { price, block_timestamp: block.time.minus_seconds(age), block_height: block.height },
+            nonce: 1,
+            decimals,
+            id: 0,
+            metadata: None,
+        };
+        OracleSourceConfig::PriceFeedContract { contract_addr: mock_feed(suite, &price).to_string() }
+    }
+
+    fn aggregated(suite: &Suite, policy: AggregationPolicy, max_age: Option<u64>) -> StdResult<AggregatedPriceResponse> {
+        let pair = CurrencyPair::new("BTC", "USD").unwrap();
+        suite.query(&QueryMsg::GetAggregatedPrice { pair, policy, max_age })
+    }
+
+    fn decimal(value: &str) -> SignedDecimal256 {
+        value.parse().unwrap()
+    }
+
+    #[test]
+    fn median_of_healthy_sources() {
+        let mut suite = Suite::new();
+        suite.set_price("BTC/USD", 10_000, 2);
+        let sources = vec![OracleSourceConfig::Slinky {}, feed(&mut suite, 10_200, 120), feed(&mut suite, 9_000, 0)];
+        suite.execute(ADMIN, &ExecuteMsg::SetOracleSources { sources }, &[]).unwrap();
+
+        let res = aggregated(&suite, AggregationPolicy::Median {}, None).unwrap();
+        assert_eq!(res.price, decimal("100"));
+        assert_eq!(res.sources.len(), 3);
+        assert_eq!(res.sources[0].source, "slinky");
+
+        // stale sources are reported but left out
+        let res = aggregated(&suite, AggregationPolicy::Median {}, Some(60)).unwrap();
+        assert_eq!(res.price, decimal("95"));
+        assert!(res.sources[1].error.is_some());
+    }
+
+    #[test]
+    fn median_of_prices_far_apart_fails_instead_of_overflowing() {
+        let mut suite = Suite::new();
+        let extreme: Int256 = "50000000000000000000000000000000000000000000000000000000000".parse().unwrap();
+        let sources = vec![feed_with_decimals(&mut suite, extreme, 0, 0), feed_with_decimals(&mut suite, -extreme, 0, 0)];
+        suite.execute(ADMIN, &ExecuteMsg::SetOracleSources { sources }, &[]).unwrap();
+
+        let err = aggregated(&suite, AggregationPolicy::Median {}, None).unwrap_err();
+        assert!(err.to_string().contains("Overflow"), "{}", err);
+    }
+
+    #[test]
+    fn falls_back_to_the_next_source() {
+        let mut suite = Suite::new();
+        let sources = vec![OracleSourceConfig::Slinky {}, feed(&mut suite, 10_100, 0), feed(&mut suite, 9_000, 0)];
+        suite.execute(ADMIN, &ExecuteMsg::SetOracleSources { sources }, &[]).unwrap();
+
+        // slinky has no BTC/USD
+        let res = aggregated(&suite, AggregationPolicy::FirstHealthy {}, None).unwrap();
+        assert_eq!(res.price, decimal("101"));
+        assert!(res.sources[0].error.is_some());
+        // stops at the first healthy source
+        assert_eq!(res.sources.len(), 2);
+
+        let sources = vec![feed(&mut suite, 10_100, 120), feed(&mut suite, 9_000, 0)];
+        suite.execute(ADMIN, &ExecuteMsg::SetOracleSources { sources }, &[]).unwrap();
+        let res = aggregated(&suite, AggregationPolicy::FirstHealthy {}, Some(60)).unwrap();
+        assert_eq!(res.price, decimal("90"));
+        assert!(res.sources[0].error.as_ref().unwrap().contains("120 seconds old"));
+    }
+
+    #[test]
+    fn max_deviation_rejects_outliers() {
+        let mut suite = Suite::new();
+        suite.set_price("BTC/USD", 10_000, 2);
+        let sources = vec![OracleSourceConfig::Slinky {}, feed(&mut suite, 10_050, 0), feed(&mut suite, 9_000, 0)];
+        suite.execute(ADMIN, &ExecuteMsg::SetOracleSources { sources }, &[]).unwrap();
+
+        // 90 is 1000 bps below the median of 100
+        assert!(aggregated(&suite, AggregationPolicy::MaxDeviation { max_deviation_bps: 999 }, None).is_err());
+        let res = aggregated(&suite, AggregationPolicy::MaxDeviation { max_deviation_bps: 1_000 }, None).unwrap();
+        assert_eq!(res.price, decimal("100"));
+    }
+
+    #[test]
+    fn fails_without_healthy_sources() {
+        let mut suite = Suite::new();
+        let sources = vec![OracleSourceConfig::Slinky {}];
+        suite.execute(ADMIN, &ExecuteMsg::SetOracleSources { sources }, &[]).unwrap();
+        assert!(aggregated(&suite, AggregationPolicy::Median {}, None).is_err());
+
+        assert!(suite.execute(ADMIN, &ExecuteMsg::SetOracleSources { sources: vec![] }, &[]).is_err());
+    }
+}
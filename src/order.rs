@@ -0,0 +1,672 @@
+use std::collections::BTreeSet;
+
+use cosmwasm_std::{
+    to_json_binary, Addr, Api, BankMsg, Binary, Coin, Coins, CosmosMsg, Deps, DepsMut, Env, MessageInfo, Order, Response,
+    SignedDecimal256, StdError, StdResult, Storage, Timestamp, WasmMsg,
+};
+use cw_storage_plus::Bound;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::currency_pair::CurrencyPair;
+use crate::error::ContractError;
+use crate::events::{price_events, price_rejected_event};
+use crate::math::price_to_decimal;
+use crate::state::Contract;
+
+const DEFAULT_LIMIT: u32 = 10;
+const MAX_LIMIT: u32 = 30;
+
+/// Messages dispatched by this contract once a pair's price crosses a threshold.
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct ConditionalOrder {
+    pub id: u64,
+    pub owner: Addr,
+    /// {Base}/{Quote}
+    pub pair: String,
+    pub condition: PriceCondition,
+    /// in whole quote units, compared against the decimal price
+    pub threshold: SignedDecimal256,
+    /// when set, the condition must also hold for the time weighted average price
+    /// over this many seconds after it is first met
+    pub twap_window: Option<u64>,
+    pub actions: Vec<OrderAction>,
+    /// funds sent on creation, spent by `actions` and refunded to the owner otherwise
+    pub escrow: Vec<Coin>,
+    pub expires_at: Timestamp,
+    /// set while a twap confirmation is running
+    pub twap: Option<TwapAccumulator>,
+}
+
+/// What a fired order does, paid from its escrow.
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum OrderAction {
+    Bank {
+        to_address: String,
+        amount: Vec<Coin>,
+    },
+    /// executes `contract_addr` with `ConditionalOrderExecuteMsg::ConditionalOrderFired` wrapping `msg`,
+    /// so the receiver knows the call comes from an order and whose it is
+    Execute {
+        contract_addr: String,
+        msg: Binary,
+        funds: Vec<Coin>,
+    },
+}
+
+/// Message dispatched by `OrderAction::Execute`.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum ConditionalOrderExecuteMsg {
+    ConditionalOrderFired {
+        order_id: u64,
+        owner: Addr,
+        msg: Binary,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, JsonSchema, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum PriceCondition {
+    /// price >= threshold
+    Above,
+    /// price <= threshold
+    Below,
+}
+
+/// Time weighted price sampled at every trigger since the condition was first met.
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct TwapAccumulator {
+    pub started_at: Timestamp,
+    pub last_sampled_at: Timestamp,
+    pub last_price: SignedDecimal256,
+    /// sum of every sampled price times the seconds since the sample before it, up to `last_sampled_at`,
+    /// the price that started the confirmation has no weight
+    pub cumulative: SignedDecimal256,
+}
+
+impl PriceCondition {
+    fn is_met(&self, price: SignedDecimal256, threshold: SignedDecimal256) -> bool {
+        match self {
+            PriceCondition::Above => price >= threshold,
+            PriceCondition::Below => price <= threshold,
+        }
+    }
+}
+
+impl<'a> Contract<'a> {
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn create_order(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        pair: CurrencyPair,
+        condition: PriceCondition,
+        threshold: SignedDecimal256,
+        twap_window: Option<u64>,
+        actions: Vec<OrderAction>,
+        expires_at: Timestamp,
+    ) -> Result<Response, ContractError> {
+        if actions.is_empty() {
+            return Err(ContractError::EmptyOrderActions {});
+        }
+        if expires_at <= env.block.time {
+            return Err(ContractError::InvalidExpiry {});
+        }
+        if twap_window == Some(0) {
+            return Err(ContractError::InvalidTwapWindow {});
+        }
+        let escrow = Coins::try_from(info.funds).map_err(|err| StdError::generic_err(err.to_string()))?;
+        // the actions run as this contract, they may only spend what the owner escrowed
+        validate_actions(deps.api, &env, &actions)?;
+        unspent_escrow(&escrow, &actions)?;
+        // fails on unknown pairs
+        self.get_price(deps.as_ref(), env, pair.clone())?;
+
+        let id = self.order_count.may_load(deps.storage)?.unwrap_or_default() + 1;
+        self.order_count.save(deps.storage, &id)?;
+        let order = ConditionalOrder {
+            id,
+            owner: info.sender,
+            pair: pair.to_string(),
+            condition,
+            threshold,
+            twap_window,
+            actions,
+            escrow: escrow.into_vec(),
+            expires_at,
+            twap: None,
+        };
+        self.orders.save(deps.storage, id, &order)?;
+        self.orders_by_owner.save(deps.storage, (&order.owner, id), &())?;
+
+        Ok(Response::new()
+            .add_attribute("action", "create_order")
+            .add_attribute("order_id", id.to_string())
+            .add_attribute("pair", order.pair))
+    }
+
+    pub(crate) fn cancel_order(
+        &self,
+        deps: DepsMut,
+        _env: Env,
+        info: MessageInfo,
+        id: u64,
+    ) -> Result<Response, ContractError> {
+        let order = self.orders.load(deps.storage, id)?;
+        if order.owner != info.sender {
+            return Err(ContractError::Unauthorized {});
+        }
+        self.remove_order(deps.storage, &order);
+
+        Ok(Response::new()
+            .add_attribute("action", "cancel_order")
+            .add_attribute("order_id", id.to_string())
+            .add_messages(refund(&order.owner, order.escrow)))
+    }
+
+    // permissionless, fires the given orders whose condition is met and refunds expired ones
+    pub(crate) fn trigger(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        ids: Vec<u64>,
+    ) -> Result<Response, ContractError> {
+        if ids.is_empty() {
+            return Err(ContractError::EmptyOrderIds {});
+        }
+        // each order is handled once, orders already fired, expired or cancelled are skipped
+        let ids: BTreeSet<u64> = ids.into_iter().collect();
+
+        // expired orders are refunded before any price is read, so they never wait on a failing pair
+        let mut messages: Vec<CosmosMsg> = vec![];
+        let mut expired = vec![];
+        let mut live = vec![];
+        for id in ids {
+            let order = match self.orders.may_load(deps.storage, id)? {
+                Some(order) => order,
+                None => continue,
+            };
+            if order.expires_at <= env.block.time {
+                self.remove_order(deps.storage, &order);
+                messages.extend(refund(&order.owner, order.escrow).map(CosmosMsg::from));
+                expired.push(id.to_string());
+            } else {
+                live.push(id);
+            }
+        }
+
+        let mut pair_ids: Vec<String> = vec![];
+        for id in live.iter() {
+            let order = self.orders.load(deps.storage, *id)?;
+            if !pair_ids.contains(&order.pair) {
+                pair_ids.push(order.pair);
+            }
+        }
+        // a pair that cannot be priced only holds back its own orders
+        let results = if pair_ids.is_empty() { Ok(vec![]) } else { self.get_price_results(deps.as_ref(), env.clone(), &pair_ids) };
+        let results = match results {
+            Ok(results) => results,
+            // pausing everything holds back every order, expired ones are still refunded
+            Err(ContractError::Paused { .. }) => vec![],
+            Err(err) => return Err(err),
+        };
+        let mut results = results.into_iter();
+        let mut priced_pairs = vec![];
+        let mut prices = vec![];
+        let mut skipped_pairs = vec![];
+        let mut rejected = vec![];
+        for pair_id in pair_ids {
+            match results.next() {
+                Some(Ok(Some(price))) => {
+                    priced_pairs.push(pair_id);
+                    prices.push(price);
+                }
+                Some(Err(err)) => {
+                    rejected.extend(price_rejected_event("trigger", &err));
+                    skipped_pairs.push(pair_id);
+                }
+                _ => skipped_pairs.push(pair_id),
+            }
+        }
+        let recorded = self.record_prices(deps.storage, &env, &info.sender, &priced_pairs, &prices)?;
+
+        let mut fired = vec![];
+        for id in live {
+            let mut order = self.orders.load(deps.storage, id)?;
+            let price = match priced_pairs.iter().position(|pair_id| pair_id == &order.pair) {
+                Some(index) => &prices[index],
+                None => continue,
+            };
+            let price = price_to_decimal(price.price.price, price.decimals)?;
+            if !order.sample(&env, price)? {
+                self.orders.save(deps.storage, order.id, &order)?;
+                continue;
+            }
+
+            self.remove_order(deps.storage, &order);
+            let escrow = Coins::try_from(order.escrow.clone()).map_err(|err| StdError::generic_err(err.to_string()))?;
+            let unspent = unspent_escrow(&escrow, &order.actions)?;
+            messages.extend(order.messages()?);
+            messages.extend(refund(&order.owner, unspent.into_vec()).map(CosmosMsg::from));
+            fired.push(order.id.to_string());
+        }
+
+        Ok(Response::new()
+            .add_attribute("action", "trigger")
+            .add_attributes((!fired.is_empty()).then(|| ("fired", fired.join(","))))
+            .add_attributes((!expired.is_empty()).then(|| ("expired", expired.join(","))))
+            .add_attributes((!skipped_pairs.is_empty()).then(|| ("skipped_pairs", skipped_pairs.join(","))))
+            .add_attribute("useful", recorded.keeper.useful.to_string())
+            .add_attributes((!recorded.deferred_settlements.is_empty()).then(|| ("deferred_settlements", recorded.deferred_settlements.join(","))))
+            .add_events(price_events("trigger", &priced_pairs, &prices))
+            .add_events(rejected)
+            .add_messages(messages)
+            .add_messages(recorded.keeper.payout))
+    }
+
+    fn remove_order(&self, storage: &mut dyn Storage, order: &ConditionalOrder) {
+        self.orders.remove(storage, order.id);
+        self.orders_by_owner.remove(storage, (&order.owner, order.id));
+    }
+
+    pub(crate) fn get_order(&self, deps: Deps, _env: Env, id: u64) -> Result<ConditionalOrder, ContractError> {
+        let order = self.orders.load(deps.storage, id)?;
+        self.assert_pairs_not_paused(deps.storage, std::slice::from_ref(&order.pair))?;
+        Ok(order)
+    }
+
+    pub(crate) fn get_orders(&self, deps: Deps, _env: Env, owner: Option<String>, start_after: Option<u64>, limit: Option<u32>) -> Result<OrdersResponse, ContractError> {
+        let owner = owner.map(|owner| deps.api.addr_validate(&owner)).transpose()?;
+        let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+        let start = start_after.map(Bound::exclusive);
+        let orders = match owner {
+            Some(owner) => self.orders_by_owner
+                .prefix(&owner)
+                .keys(deps.storage, start, None, Order::Ascending)
+                .take(limit)
+                .map(|id| self.orders.load(deps.storage, id?))
+                .collect::<StdResult<Vec<_>>>()?,
+            None => self.orders
+                .range(deps.storage, start, None, Order::Ascending)
+                .take(limit)
+                .map(|item| item.map(|(_, order)| order))
+                .collect::<StdResult<Vec<_>>>()?,
+        };
+        // orders carry the sampled prices of their pair
+        let pair_ids: Vec<String> = orders.iter().map(|order| order.pair.clone()).collect();
+        self.assert_pairs_not_paused(deps.storage, &pair_ids)?;
+
+        Ok(OrdersResponse { orders })
+    }
+}
+
+impl ConditionalOrder {
+    // the actions as messages of this contract
+    fn messages(&self) -> StdResult<Vec<CosmosMsg>> {
+        self.actions
+            .iter()
+            .map(|action| match action {
+                OrderAction::Bank { to_address, amount } => Ok(BankMsg::Send {
+                    to_address: to_address.clone(),
+                    amount: amount.clone(),
+                }
+                .into()),
+                OrderAction::Execute { contract_addr, msg, funds } => Ok(WasmMsg::Execute {
+                    contract_addr: contract_addr.clone(),
+                    msg: to_json_binary(&ConditionalOrderExecuteMsg::ConditionalOrderFired {
+                        order_id: self.id,
+                        owner: self.owner.clone(),
+                        msg: msg.clone(),
+                    })?,
+                    funds: funds.clone(),
+                }
+                .into()),
+            })
+            .collect()
+    }
+
+    // records the current price, true once the order should fire
+    fn sample(&mut self, env: &Env, price: SignedDecimal256) -> StdResult<bool> {
+        let window = match self.twap_window {
+            Some(window) => window,
+            None => return Ok(self.condition.is_met(price, self.threshold)),
+        };
+
+        let mut twap = match self.twap.take() {
+            Some(twap) => twap,
+            None => {
+                if self.condition.is_met(price, self.threshold) {
+                    self.twap = Some(TwapAccumulator {
+                        started_at: env.block.time,
+                        last_sampled_at: env.block.time,
+                        last_price: price,
+                        cumulative: SignedDecimal256::zero(),
+                    });
+                }
+                return Ok(false);
+            }
+        };
+
+        // the current price covers the time since the previous sample, so a single price meeting the
+        // condition cannot confirm itself and the average only holds prices sampled after it
+        let elapsed = env.block.time.seconds() - twap.last_sampled_at.seconds();
+        twap.cumulative = twap.cumulative.checked_add(price.checked_mul(seconds(elapsed))?)?;
+        twap.last_sampled_at = env.block.time;
+        twap.last_price = price;
+
+        let duration = env.block.time.seconds() - twap.started_at.seconds();
+        if duration < window {
+            self.twap = Some(twap);
+            return Ok(false);
+        }
+
+        let average = twap.cumulative.checked_div(seconds(duration))
+            .map_err(|err| StdError::generic_err(err.to_string()))?;
+        if self.condition.is_met(average, self.threshold) {
+            return Ok(true);
+        }
+        // not confirmed, start over from the current price
+        if self.condition.is_met(price, self.threshold) {
+            self.twap = Some(TwapAccumulator {
+                started_at: env.block.time,
+                last_sampled_at: env.block.time,
+                last_price: price,
+                cumulative: SignedDecimal256::zero(),
+            });
+        }
+        Ok(false)
+    }
+}
+
+fn seconds(seconds: u64) -> SignedDecimal256 {
+    SignedDecimal256::from_ratio(seconds, 1u64)
+}
+
+// actions may not execute this contract, which holds every order's escrow
+fn validate_actions(api: &dyn Api, env: &Env, actions: &[OrderAction]) -> Result<(), ContractError> {
+    for action in actions {
+        match action {
+            OrderAction::Bank { to_address, .. } => {
+                api.addr_validate(to_address)?;
+            }
+            OrderAction::Execute { contract_addr, .. } => {
+                if api.addr_validate(contract_addr)? == env.contract.address {
+                    return Err(ContractError::UnsupportedOrderAction {});
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+// escrow left after paying for `actions`, fails when they spend more than escrowed
+fn unspent_escrow(escrow: &Coins, actions: &[OrderAction]) -> Result<Coins, ContractError> {
+    let mut unspent = escrow.clone();
+    for action in actions {
+        let spent = match action {
+            OrderAction::Bank { amount, .. } => amount,
+            OrderAction::Execute { funds, .. } => funds,
+        };
+        for coin in spent {
+            unspent.sub(coin.clone()).map_err(|_| ContractError::InsufficientEscrow {})?;
+        }
+    }
+    Ok(unspent)
+}
+
+fn refund(owner: &Addr, funds: Vec<Coin>) -> Option<BankMsg> {
+    if funds.is_empty() {
+        return None;
+    }
+    Some(BankMsg::Send { to_address: owner.to_string(), amount: funds })
+}
+
+#[derive(Clone, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct OrdersResponse {
+    pub orders: Vec<ConditionalOrder>,
+}
+
+#[cfg(test)]
+mod tests {
+    use cosmwasm_std::{coin, coins, Empty};
+    use cw_multi_test::{AppResponse, ContractWrapper, Executor};
+    use cw_storage_plus::Item;
+
+    use super::*;
+    use crate::msgs::{ExecuteMsg, QueryMsg};
+    use crate::testing::{attribute, error_of, Suite, ADMIN, DENOM, KEEPER, USER};
+
+    const RECIPIENT: &str = "recipient";
+
+    // a contract storing every conditional order message it receives
+    fn mock_receiver(suite: &mut Suite) -> Addr {
+        const RECEIVED: Item<Vec<ConditionalOrderExecuteMsg>> = Item::new("received");
+        let code = ContractWrapper::new(
+            |deps: DepsMut, _: Env, _: MessageInfo, msg: ConditionalOrderExecuteMsg| -> StdResult<Response> {
+                let mut received = RECEIVED.may_load(deps.storage)?.unwrap_or_default();
+                received.push(msg);
+                RECEIVED.save(deps.storage, &received).map(|_| Response::new())
+            },
+            |_: DepsMut, _: Env, _: MessageInfo, _: Empty| -> StdResult<Response> { Ok(Response::new()) },
+            |deps: Deps, _: Env, _: Empty| -> StdResult<Binary> { to_json_binary(&RECEIVED.may_load(deps.storage)?.unwrap_or_default()) },
+        );
+        let code_id = suite.app.store_code(Box::new(code));
+        suite.app.instantiate_contract(code_id, Addr::unchecked(ADMIN), &Empty {}, &[], "receiver", None).unwrap()
+    }
+
+    fn send(amount: u128) -> OrderAction {
+        OrderAction::Bank { to_address: RECIPIENT.to_string(), amount: coins(amount, DENOM) }
+    }
+
+    fn create_order(suite: &mut Suite, pair: &str, threshold: i64, twap_window: Option<u64>, actions: Vec<OrderAction>, escrow: u128) -> anyhow::Result<AppResponse> {
+        let msg = ExecuteMsg::CreateOrder {
+            pair: pair.parse().unwrap(),
+            condition: PriceCondition::Below,
+            threshold: SignedDecimal256::from_ratio(threshold, 1i64),
+            twap_window,
+            actions,
+            expires_at: suite.app.block_info().time.plus_seconds(1_000),
+        };
+        let funds = if escrow == 0 { vec![] } else { coins(escrow, DENOM) };
+        suite.execute(USER, &msg, &funds)
+    }
+
+    fn trigger(suite: &mut Suite, ids: Vec<u64>) -> AppResponse {
+        suite.execute(KEEPER, &ExecuteMsg::Trigger { ids }, &[]).unwrap()
+    }
+
+    fn order_exists(suite: &Suite, id: u64) -> bool {
+        suite.query::<ConditionalOrder>(&QueryMsg::GetOrder { id }).is_ok()
+    }
+
+    #[test]
+    fn fires_once_the_condition_is_met() {
+        let mut suite = Suite::new();
+        suite.set_price("BTC/USD", 110, 0);
+        create_order(&mut suite, "BTC/USD", 100, None, vec![send(300)], 1_000).unwrap();
+
+        let res = trigger(&mut suite, vec![1]);
+        assert_eq!((attribute(&res, "fired"), attribute(&res, "useful")), (String::new(), "false".to_string()));
+        assert!(order_exists(&suite, 1));
+
+        suite.advance(5);
+        suite.set_price("BTC/USD", 100, 0);
+        let before = suite.balance(USER);
+        let res = trigger(&mut suite, vec![1]);
+        assert_eq!(attribute(&res, "fired"), "1");
+        // the keeper advanced the tracked nonce
+        assert_eq!(attribute(&res, "useful"), "true");
+        assert_eq!(suite.balance(RECIPIENT), 300);
+        assert_eq!(suite.balance(USER), before + 700);
+        assert!(!order_exists(&suite, 1));
+    }
+
+    #[test]
+    fn executes_are_wrapped_with_the_owner() {
+        let mut suite = Suite::new();
+        suite.set_price("BTC/USD", 90, 0);
+        let receiver = mock_receiver(&mut suite);
+        let action = OrderAction::Execute { contract_addr: receiver.to_string(), msg: Binary::from(b"{\"buy\":{}}"), funds: coins(10, DENOM) };
+        create_order(&mut suite, "BTC/USD", 100, None, vec![action], 10).unwrap();
+
+        trigger(&mut suite, vec![1]);
+        let received: Vec<ConditionalOrderExecuteMsg> = suite.app.wrap().query_wasm_smart(&receiver, &Empty {}).unwrap();
+        assert_eq!(received, vec![ConditionalOrderExecuteMsg::ConditionalOrderFired {
+            order_id: 1,
+            owner: Addr::unchecked(USER),
+            msg: Binary::from(b"{\"buy\":{}}"),
+        }]);
+        assert_eq!(suite.balance(receiver.as_str()), 10);
+    }
+
+    #[test]
+    fn create_order_validation() {
+        let mut suite = Suite::new();
+        suite.set_price("BTC/USD", 90, 0);
+        let err = create_order(&mut suite, "BTC/USD", 100, None, vec![], 0).unwrap_err();
+        assert_eq!(error_of(err), "At least one action is required");
+        let err = create_order(&mut suite, "BTC/USD", 100, None, vec![send(11)], 10).unwrap_err();
+        assert_eq!(error_of(err), "Order actions spend more than the escrowed funds");
+        let oracle = OrderAction::Execute { contract_addr: suite.contract.to_string(), msg: Binary::from(b"{}"), funds: vec![] };
+        let err = create_order(&mut suite, "BTC/USD", 100, None, vec![oracle], 0).unwrap_err();
+        assert_eq!(error_of(err), "Orders cannot execute this contract");
+        let invalid = OrderAction::Bank { to_address: "".to_string(), amount: vec![coin(1, DENOM)] };
+        assert!(create_order(&mut suite, "BTC/USD", 100, None, vec![invalid], 1).is_err());
+        assert!(create_order(&mut suite, "SOL/USD", 100, None, vec![send(1)], 1).is_err());
+        assert!(create_order(&mut suite, "BTC/USD", 100, Some(0), vec![send(1)], 1).is_err());
+    }
+
+    #[test]
+    fn duplicate_and_missing_ids_are_skipped() {
+        let mut suite = Suite::new();
+        suite.set_price("BTC/USD", 90, 0);
+        create_order(&mut suite, "BTC/USD", 100, None, vec![send(100)], 100).unwrap();
+
+        let res = trigger(&mut suite, vec![1, 1, 42, 1]);
+        assert_eq!(attribute(&res, "fired"), "1");
+        assert_eq!(suite.balance(RECIPIENT), 100);
+        let res = trigger(&mut suite, vec![1]);
+        assert_eq!(attribute(&res, "fired"), "");
+        assert_eq!(suite.balance(RECIPIENT), 100);
+    }
+
+    #[test]
+    fn failing_pairs_only_hold_back_their_orders() {
+        let mut suite = Suite::new();
+        suite.set_price("BTC/USD", 90, 0);
+        suite.set_price("ETH/USD", 90, 0);
+        create_order(&mut suite, "BTC/USD", 100, None, vec![send(100)], 100).unwrap();
+        create_order(&mut suite, "ETH/USD", 100, None, vec![send(100)], 100).unwrap();
+        let target = crate::msgs::PauseTarget::Pair { pair_id: "ETH/USD".parse().unwrap() };
+        suite.execute(ADMIN, &ExecuteMsg::Pause { target: target.clone() }, &[]).unwrap();
+
+        let res = trigger(&mut suite, vec![1, 2]);
+        assert_eq!(attribute(&res, "fired"), "1");
+        assert_eq!(attribute(&res, "skipped_pairs"), "ETH/USD");
+        suite.execute(ADMIN, &ExecuteMsg::Unpause { target }, &[]).unwrap();
+        assert!(order_exists(&suite, 2));
+    }
+
+    #[test]
+    fn expired_orders_are_refunded_before_pricing() {
+        let mut suite = Suite::new();
+        suite.set_price("BTC/USD", 110, 0);
+        suite.set_price("ETH/USD", 110, 0);
+        create_order(&mut suite, "BTC/USD", 100, None, vec![send(100)], 100).unwrap();
+        create_order(&mut suite, "ETH/USD", 100, None, vec![send(100)], 100).unwrap();
+        suite.advance(1_000);
+        suite.slinky.remove_pair("BTC/USD");
+        suite.execute(ADMIN, &ExecuteMsg::Pause { target: crate::msgs::PauseTarget::All {} }, &[]).unwrap();
+
+        let before = suite.balance(USER);
+        let res = trigger(&mut suite, vec![2, 1]);
+        assert_eq!(attribute(&res, "expired"), "1,2");
+        assert_eq!(suite.balance(USER), before + 200);
+        assert!(!order_exists(&suite, 1) && !order_exists(&suite, 2));
+    }
+
+    #[test]
+    fn orders_are_paged_by_owner() {
+        let mut suite = Suite::new();
+        suite.set_price("BTC/USD", 110, 0);
+        for owner in [USER, ADMIN, USER, ADMIN, USER] {
+            let msg = ExecuteMsg::CreateOrder {
+                pair: "BTC/USD".parse().unwrap(),
+                condition: PriceCondition::Below,
+                threshold: SignedDecimal256::from_ratio(100u64, 1u64),
+                twap_window: None,
+                actions: vec![send(1)],
+                expires_at: suite.app.block_info().time.plus_seconds(1_000),
+            };
+            suite.execute(owner, &msg, &coins(1, DENOM)).unwrap();
+        }
+        suite.execute(USER, &ExecuteMsg::CancelOrder { id: 3 }, &[]).unwrap();
+
+        let ids = |suite: &Suite, owner: Option<&str>, start_after: Option<u64>, limit: Option<u32>| -> Vec<u64> {
+            let msg = QueryMsg::GetOrders { owner: owner.map(str::to_string), start_after, limit };
+            suite.query::<OrdersResponse>(&msg).unwrap().orders.into_iter().map(|order| order.id).collect()
+        };
+        assert_eq!(ids(&suite, None, None, None), vec![1, 2, 4, 5]);
+        assert_eq!(ids(&suite, Some(USER), None, None), vec![1, 5]);
+        assert_eq!(ids(&suite, Some(ADMIN), None, Some(1)), vec![2]);
+        assert_eq!(ids(&suite, Some(ADMIN), Some(2), Some(1)), vec![4]);
+        assert_eq!(ids(&suite, Some(ADMIN), Some(4), None), Vec::<u64>::new());
+    }
+
+    #[test]
+    fn twap_confirmation() {
+        let mut suite = Suite::new();
+        suite.set_price("BTC/USD", 90, 0);
+        create_order(&mut suite, "BTC/USD", 100, Some(60), vec![send(100)], 100).unwrap();
+
+        trigger(&mut suite, vec![1]);
+        assert!(suite.query::<ConditionalOrder>(&QueryMsg::GetOrder { id: 1 }).unwrap().twap.is_some());
+        suite.advance(30);
+        suite.set_price("BTC/USD", 120, 0);
+        assert_eq!(attribute(&trigger(&mut suite, vec![1]), "fired"), "");
+        // the twap (120 * 30 + 120 * 30) / 60 = 120 and the spot price miss the condition, the confirmation is dropped
+        suite.advance(30);
+        assert_eq!(attribute(&trigger(&mut suite, vec![1]), "fired"), "");
+        assert_eq!(suite.query::<ConditionalOrder>(&QueryMsg::GetOrder { id: 1 }).unwrap().twap, None);
+
+        suite.set_price("BTC/USD", 80, 0);
+        trigger(&mut suite, vec![1]);
+        suite.advance(60);
+        assert_eq!(attribute(&trigger(&mut suite, vec![1]), "fired"), "1");
+    }
+
+    #[test]
+    fn twap_confirmation_ignores_reverted_spikes() {
+        let mut suite = Suite::new();
+        suite.set_price("BTC/USD", 110, 0);
+        create_order(&mut suite, "BTC/USD", 100, Some(60), vec![send(100)], 100).unwrap();
+
+        // a one block dip starts the confirmation
+        suite.set_price("BTC/USD", 50, 0);
+        trigger(&mut suite, vec![1]);
+        assert!(suite.query::<ConditionalOrder>(&QueryMsg::GetOrder { id: 1 }).unwrap().twap.is_some());
+
+        // the price reverted before the next trigger, the dip alone does not confirm the order
+        suite.advance(60);
+        suite.set_price("BTC/USD", 110, 0);
+        assert_eq!(attribute(&trigger(&mut suite, vec![1]), "fired"), "");
+        assert_eq!(suite.query::<ConditionalOrder>(&QueryMsg::GetOrder { id: 1 }).unwrap().twap, None);
+        assert_eq!(suite.balance(RECIPIENT), 0);
+
+        // a dip within the window is averaged with the reverted price
+        suite.set_price("BTC/USD", 50, 0);
+        trigger(&mut suite, vec![1]);
+        suite.advance(10);
+        suite.set_price("BTC/USD", 50, 0);
+        trigger(&mut suite, vec![1]);
+        suite.advance(50);
+        suite.set_price("BTC/USD", 140, 0);
+        // (50 * 10 + 140 * 50) / 60 = 125
+        assert_eq!(attribute(&trigger(&mut suite, vec![1]), "fired"), "");
+        assert!(order_exists(&suite, 1));
+    }
+}
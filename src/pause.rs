@@ -0,0 +1,357 @@
+use cosmwasm_std::{Addr, Deps, DepsMut, Env, MessageInfo, Response, StdResult, Storage};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::error::ContractError;
+use crate::msgs::{PauseTarget, QueryMsg};
+use crate::state::Contract;
+
+/// every `QueryMsg::kind`, the query kinds that can be paused
+pub const QUERY_KINDS: &[&str] = &[
+    "get_price",
+    "get_prices",
+    "get_all_currency_pairs",
+    "get_prices_lenient",
+    "get_all_prices",
+    "get_denom_metadata",
+    "convert_amount",
+    "value_portfolio",
+    "get_oracle_sources",
+    "get_aggregated_price",
+    "latest_round_data",
+    "get_round_data",
+    "decimals",
+    "description",
+    "get_reference_data",
+    "get_reference_data_bulk",
+    "price_feed",
+    "get_subscription",
+    "get_subscriptions",
+    "get_channel_subscriptions",
+    "get_keeper",
+    "get_keepers",
+    "get_reward_pool",
+    "get_pause_state",
+    "get_guardians",
+    "get_freshness_thresholds",
+    "get_order",
+    "get_orders",
+    "get_settlement",
+    "get_receipt",
+    "get_cumulative",
+    "get_twap",
+    "get_ema",
+    "get_candle_intervals",
+    "get_candles",
+    "get_pair_bounds",
+];
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug, Default)]
+pub struct PauseState {
+    /// pauses every price read, including executes and ibc
+    pub all: bool,
+    /// paused {Base}/{Quote} pairs
+    pub pairs: Vec<String>,
+    /// paused query kinds, named after the query message, e.g. "get_price"
+    pub queries: Vec<String>,
+}
+
+impl<'a> Contract<'a> {
+    pub(crate) fn set_guardians(
+        &self,
+        deps: DepsMut,
+        _env: Env,
+        info: MessageInfo,
+        guardians: Vec<String>,
+    ) -> Result<Response, ContractError> {
+        self.assert_admin(&deps, &info)?;
+        let guardians = guardians
+            .iter()
+            .map(|guardian| deps.api.addr_validate(guardian))
+            .collect::<StdResult<Vec<_>>>()?;
+        self.guardians.save(deps.storage, &guardians)?;
+
+        Ok(Response::new()
+            .add_attribute("action", "set_guardians")
+            .add_attribute("count", guardians.len().to_string()))
+    }
+
+    // admin or guardians
+    pub(crate) fn pause(
+        &self,
+        deps: DepsMut,
+        _env: Env,
+        info: MessageInfo,
+        target: PauseTarget,
+    ) -> Result<Response, ContractError> {
+        let config = self.config.load(deps.storage)?;
+        let guardians = self.guardians.may_load(deps.storage)?.unwrap_or_default();
+        if config.admin != info.sender && !guardians.contains(&info.sender) {
+            return Err(ContractError::Unauthorized {});
+        }
+
+        let mut state = self.pause_state.may_load(deps.storage)?.unwrap_or_default();
+        match &target {
+            PauseTarget::All {} => state.all = true,
+            PauseTarget::Pair { pair_id } => add_unique(&mut state.pairs, &pair_id.to_string()),
+            PauseTarget::Query { kind } => {
+                if !QUERY_KINDS.contains(&kind.as_str()) {
+                    return Err(ContractError::UnknownQueryKind { kind: kind.clone() });
+                }
+                add_unique(&mut state.queries, kind)
+            }
+        }
+        self.pause_state.save(deps.storage, &state)?;
+
+        Ok(Response::new()
+            .add_attribute("action", "pause")
+            .add_attribute("target", target.to_string())
+            .add_attribute("sender", info.sender))
+    }
+
+    // admin only
+    pub(crate) fn unpause(
+        &self,
+        deps: DepsMut,
+        _env: Env,
+        info: MessageInfo,
+        target: PauseTarget,
+    ) -> Result<Response, ContractError> {
+        self.assert_admin(&deps, &info)?;
+
+        let mut state = self.pause_state.may_load(deps.storage)?.unwrap_or_default();
+        match &target {
+            PauseTarget::All {} => state.all = false,
+            PauseTarget::Pair { pair_id } => state.pairs.retain(|pair| *pair != pair_id.to_string()),
+            PauseTarget::Query { kind } => state.queries.retain(|query| query != kind),
+        }
+        self.pause_state.save(deps.storage, &state)?;
+
+        Ok(Response::new()
+            .add_attribute("action", "unpause")
+            .add_attribute("target", target.to_string()))
+    }
+
+    // authorized by the caller, clears every pause
+    pub(crate) fn force_unpause(&self, deps: DepsMut, _env: Env) -> Result<Response, ContractError> {
+        self.pause_state.remove(deps.storage);
+        Ok(Response::new().add_attribute("action", "force_unpause"))
+    }
+
+    pub(crate) fn get_pause_state(&self, deps: Deps, _env: Env) -> StdResult<PauseState> {
+        Ok(self.pause_state.may_load(deps.storage)?.unwrap_or_default())
+    }
+
+    pub(crate) fn get_guardians(&self, deps: Deps, _env: Env) -> StdResult<Vec<Addr>> {
+        Ok(self.guardians.may_load(deps.storage)?.unwrap_or_default())
+    }
+
+    pub(crate) fn assert_query_not_paused(&self, storage: &dyn Storage, kind: &str) -> Result<(), ContractError> {
+        let state = self.pause_state.may_load(storage)?.unwrap_or_default();
+        if state.queries.iter().any(|query| query == kind) {
+            return Err(ContractError::Paused { target: format!("query {}", kind) });
+        }
+        Ok(())
+    }
+
+    // every price read goes through here, so the global pause is checked as well.
+    // a new query or execute reading prices or recorded price history must call it
+    // with the pairs it reads before touching them, and get a `kind` in `QUERY_KINDS`
+    pub(crate) fn assert_pairs_not_paused(&self, storage: &dyn Storage, pair_ids: &[String]) -> Result<(), ContractError> {
+        let state = self.pause_state.may_load(storage)?.unwrap_or_default();
+        if state.all {
+            return Err(ContractError::Paused { target: "all".to_string() });
+        }
+        if let Some(pair_id) = pair_ids.iter().find(|pair_id| state.pairs.contains(pair_id)) {
+            return Err(ContractError::Paused { target: format!("pair {}", pair_id) });
+        }
+        Ok(())
+    }
+}
+
+fn add_unique(values: &mut Vec<String>, value: &str) {
+    if !values.iter().any(|v| v == value) {
+        values.push(value.to_string());
+    }
+}
+
+impl std::fmt::Display for PauseTarget {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PauseTarget::All {} => write!(f, "all"),
+            PauseTarget::Pair { pair_id } => write!(f, "pair {}", pair_id),
+            PauseTarget::Query { kind } => write!(f, "query {}", kind),
+        }
+    }
+}
+
+impl QueryMsg {
+    /// name of the query message, used as pausable query kind
+    pub fn kind(&self) -> &'static str {
+        match self {
+            QueryMsg::GetPrice { .. } => "get_price",
+            QueryMsg::GetPrices { .. } => "get_prices",
+            QueryMsg::GetAllCurrencyPairs { .. } => "get_all_currency_pairs",
+            QueryMsg::GetPricesLenient { .. } => "get_prices_lenient",
+            QueryMsg::GetAllPrices { .. } => "get_all_prices",
+            QueryMsg::GetDenomMetadata { .. } => "get_denom_metadata",
+            QueryMsg::ConvertAmount { .. } => "convert_amount",
+            QueryMsg::ValuePortfolio { .. } => "value_portfolio",
+            QueryMsg::GetOracleSources { .. } => "get_oracle_sources",
+            QueryMsg::GetPairBounds { .. } => "get_pair_bounds",
+            QueryMsg::GetAggregatedPrice { .. } => "get_aggregated_price",
+            QueryMsg::LatestRoundData { .. } => "latest_round_data",
+            QueryMsg::GetRoundData { .. } => "get_round_data",
+            QueryMsg::Decimals { .. } => "decimals",
+            QueryMsg::Description { .. } => "description",
+            QueryMsg::GetReferenceData { .. } => "get_reference_data",
+            QueryMsg::GetReferenceDataBulk { .. } => "get_reference_data_bulk",
+            QueryMsg::PriceFeed { .. } => "price_feed",
+            QueryMsg::GetSubscription { .. } => "get_subscription",
+            QueryMsg::GetSubscriptions { .. } => "get_subscriptions",
+            QueryMsg::GetChannelSubscriptions { .. } => "get_channel_subscriptions",
+            QueryMsg::GetKeeper { .. } => "get_keeper",
+            QueryMsg::GetKeepers { .. } => "get_keepers",
+            QueryMsg::GetRewardPool { .. } => "get_reward_pool",
+            QueryMsg::GetPauseState { .. } => "get_pause_state",
+            QueryMsg::GetGuardians { .. } => "get_guardians",
+            QueryMsg::GetFreshnessThresholds { .. } => "get_freshness_thresholds",
+            QueryMsg::GetOrder { .. } => "get_order",
+            QueryMsg::GetOrders { .. } => "get_orders",
+            QueryMsg::GetSettlement { .. } => "get_settlement",
+            QueryMsg::GetReceipt { .. } => "get_receipt",
+            QueryMsg::GetCumulative { .. } => "get_cumulative",
+            QueryMsg::GetTwap { .. } => "get_twap",
+            QueryMsg::GetEma { .. } => "get_ema",
+            QueryMsg::GetCandleIntervals { .. } => "get_candle_intervals",
+            QueryMsg::GetCandles { .. } => "get_candles",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cosmwasm_std::{coins, SignedDecimal256};
+    use schemars::schema::Schema;
+
+    use super::*;
+    use crate::msgs::{ExecuteMsg, SudoMsg};
+    use crate::order::{OrderAction, PriceCondition};
+    use crate::query::GetPriceResponse;
+    use crate::testing::{error_of, Suite, ADMIN, DENOM, KEEPER, USER};
+    use crate::twap::TwapMode;
+
+    fn btc() -> crate::CurrencyPair {
+        "BTC/USD".parse().unwrap()
+    }
+
+    // every query reading BTC/USD prices, price history or prices stored with a receipt, settlement or order
+    fn btc_reads() -> Vec<QueryMsg> {
+        vec![
+            QueryMsg::GetPrice { pair: btc() },
+            QueryMsg::GetPrices { pair_ids: vec![btc()] },
+            QueryMsg::GetCumulative { pair: btc() },
+            QueryMsg::GetTwap { pair: btc(), window: 60, mode: TwapMode::Arithmetic },
+            QueryMsg::GetEma { pair: btc(), half_life: 60 },
+            QueryMsg::GetCandles { pair: btc(), interval: 60, start_after: None, limit: None },
+            QueryMsg::GetReceipt { id: 1 },
+            QueryMsg::GetSettlement { id: 1 },
+            QueryMsg::GetOrder { id: 1 },
+            QueryMsg::GetOrders { owner: Some(USER.to_string()), start_after: None, limit: None },
+        ]
+    }
+
+    fn paused_error(suite: &Suite, msg: &QueryMsg) -> String {
+        suite.query::<cosmwasm_std::Empty>(msg).map(|_| String::new()).unwrap_or_else(|err| err.to_string())
+    }
+
+    fn setup() -> Suite {
+        let mut suite = Suite::new();
+        suite.set_price("BTC/USD", 100, 0);
+        suite.set_price("ETH/USD", 10, 0);
+        let pairs = vec![btc(), "ETH/USD".parse().unwrap()];
+        suite.execute(KEEPER, &ExecuteMsg::QuoteAndRecord { pairs }, &[]).unwrap();
+        let settle_at = suite.app.block_info().time.plus_seconds(10);
+        suite.execute(USER, &ExecuteMsg::RegisterSettlement { pair: btc(), settle_at, max_delay: None }, &[]).unwrap();
+        let order = ExecuteMsg::CreateOrder {
+            pair: btc(),
+            condition: PriceCondition::Below,
+            threshold: SignedDecimal256::from_ratio(50u64, 1u64),
+            twap_window: None,
+            actions: vec![OrderAction::Bank { to_address: ADMIN.to_string(), amount: coins(1, DENOM) }],
+            expires_at: suite.app.block_info().time.plus_seconds(1_000),
+        };
+        suite.execute(USER, &order, &coins(1, DENOM)).unwrap();
+        suite.advance(60);
+        suite.execute(ADMIN, &ExecuteMsg::SetGuardians { guardians: vec![USER.to_string()] }, &[]).unwrap();
+        for msg in btc_reads() {
+            suite.query::<cosmwasm_std::Empty>(&msg).unwrap();
+        }
+        suite
+    }
+
+    #[test]
+    fn query_kinds_match_query_msg() {
+        let schema = schemars::schema_for!(QueryMsg);
+        let mut variants: Vec<String> = schema.schema.subschemas.unwrap().one_of.unwrap()
+            .into_iter()
+            .flat_map(|variant| match variant {
+                Schema::Object(object) => object.object.unwrap().required.into_iter().collect(),
+                Schema::Bool(_) => vec![],
+            })
+            .collect();
+        variants.sort();
+        let mut kinds = QUERY_KINDS.to_vec();
+        kinds.sort();
+        assert_eq!(variants, kinds);
+        assert_eq!(QueryMsg::GetPairBounds { pair: btc() }.kind(), "get_pair_bounds");
+    }
+
+    #[test]
+    fn pause_pair_blocks_every_read_of_it() {
+        let mut suite = setup();
+        let target = PauseTarget::Pair { pair_id: "btc/usd".parse().unwrap() };
+        suite.execute(USER, &ExecuteMsg::Pause { target: target.clone() }, &[]).unwrap();
+        let state: PauseState = suite.query(&QueryMsg::GetPauseState {}).unwrap();
+        assert_eq!(state.pairs, vec!["BTC/USD"]);
+
+        for msg in btc_reads() {
+            assert!(paused_error(&suite, &msg).contains("Paused: pair BTC/USD"), "{:?}", msg);
+        }
+        suite.query::<GetPriceResponse>(&QueryMsg::GetPrice { pair: "ETH/USD".parse().unwrap() }).unwrap();
+
+        // only the admin lifts pauses
+        assert_eq!(error_of(suite.execute(USER, &ExecuteMsg::Unpause { target: target.clone() }, &[]).unwrap_err()), "Unauthorized");
+        suite.execute(ADMIN, &ExecuteMsg::Unpause { target }, &[]).unwrap();
+        for msg in btc_reads() {
+            suite.query::<cosmwasm_std::Empty>(&msg).unwrap();
+        }
+    }
+
+    #[test]
+    fn pause_all_blocks_reads_and_records() {
+        let mut suite = setup();
+        suite.execute(USER, &ExecuteMsg::Pause { target: PauseTarget::All {} }, &[]).unwrap();
+        for msg in btc_reads() {
+            assert!(paused_error(&suite, &msg).contains("Paused: all"), "{:?}", msg);
+        }
+        let err = suite.execute(KEEPER, &ExecuteMsg::QuoteAndRecord { pairs: vec![btc()] }, &[]).unwrap_err();
+        assert_eq!(error_of(err), "Paused: all");
+
+        suite.sudo(&SudoMsg::ForceUnpause {}).unwrap();
+        suite.query::<GetPriceResponse>(&QueryMsg::GetPrice { pair: btc() }).unwrap();
+    }
+
+    #[test]
+    fn pause_query_kind() {
+        let mut suite = setup();
+        let err = suite.execute(USER, &ExecuteMsg::Pause { target: PauseTarget::Query { kind: "get_pricez".to_string() } }, &[]).unwrap_err();
+        assert_eq!(error_of(err), "Unknown query kind get_pricez");
+        assert_eq!(error_of(suite.execute(KEEPER, &ExecuteMsg::Pause { target: PauseTarget::All {} }, &[]).unwrap_err()), "Unauthorized");
+
+        suite.execute(USER, &ExecuteMsg::Pause { target: PauseTarget::Query { kind: "get_twap".to_string() } }, &[]).unwrap();
+        let twap = QueryMsg::GetTwap { pair: btc(), window: 60, mode: TwapMode::Arithmetic };
+        assert!(paused_error(&suite, &twap).contains("Paused: query get_twap"));
+        suite.query::<GetPriceResponse>(&QueryMsg::GetPrice { pair: btc() }).unwrap();
+    }
+}
@@ -1,17 +1,27 @@
 use std::str::FromStr;
 
-use cosmwasm_std::{to_json_binary, Binary, Deps, Empty, Env, QueryRequest, StdResult, Timestamp, Uint256};
+use cosmwasm_std::{to_json_binary, Binary, Coin, Decimal256, Deps, Empty, Env, QueryRequest, StdError, StdResult, Int256, SignedDecimal256, Storage, Timestamp, Uint256};
 
-use crate::state::Contract;
-use crate::msgs::QueryMsg;
-use crate::slinky_oracle::{GetAllCurrencyPairsRequest, GetPricesRequest, GetPriceRequest, CurrencyPair};
-use crate::timestamp::convert_iso_string_to_timestamp;
+use crate::currency_pair::{dedup_pairs, normalize_ticker, CurrencyPair};
+use crate::deserialize::{deserialize_timestamp, deserialize_u64};
+use crate::error::ContractError;
+use crate::math::{convert_amount, deviation_bps, median, price_to_decimal, unsigned_price, value_of};
+use crate::state::{Contract, DenomMetadata, FreshnessThresholds};
+use crate::msgs::{AggregationPolicy, QueryMsg, RoundingMode, SortOrder};
+use crate::oracle_source::OracleSourceConfig;
+use crate::slinky_oracle::{GetAllCurrencyPairsRequest, GetPricesRequest, GetPriceRequest, CurrencyPair as ProtoCurrencyPair};
 use protobuf::{Message, MessageField};
 
-impl<'a> Contract {
-    fn get_price(&self, deps: Deps, _env: Env, base: String, quote: String) -> StdResult<GetPriceResponse> {
+const DEFAULT_LIMIT: u32 = 10;
+const MAX_LIMIT: u32 = 30;
+
+impl<'a> Contract<'a> {
+    pub(crate) fn get_price(&self, deps: Deps, env: Env, pair: CurrencyPair) -> Result<GetPriceResponse, ContractError> {
+        let id = pair.to_string();
+        self.assert_pairs_not_paused(deps.storage, std::slice::from_ref(&id))?;
+
         let request = GetPriceRequest { 
-            currency_pair:MessageField::some(CurrencyPair{ Base: base, Quote: quote, special_fields: ::protobuf::SpecialFields::new() }),
+            currency_pair:MessageField::some(ProtoCurrencyPair{ Base: pair.base().to_string(), Quote: pair.quote().to_string(), special_fields: ::protobuf::SpecialFields::new() }),
             special_fields: ::protobuf::SpecialFields::new()
         };
         let bytes = request.write_to_bytes().unwrap();
@@ -19,10 +29,102 @@ impl<'a> Contract {
         let data = Binary::from(bytes);
         let request = QueryRequest::Stargate{path: "/slinky.oracle.v1.Query/GetPrice".to_string(), data};
         let res: GetPriceResponseRaw = deps.querier.query(&request)?;
-        Ok(convert_raw_price_response(&res))
+        let thresholds = self.freshness_thresholds(deps.storage)?;
+        let res = convert_raw_price_response(&res)?.ok_or_else(|| StdError::not_found(format!("price of {}", id)))?;
+        self.assert_within_bounds(deps.storage, &id, &res)?;
+        Ok(with_metadata(&env, &thresholds, res))
+    }
+
+    pub(crate) fn get_prices(&self, deps: Deps, env: Env, pair_ids: Vec<String>) -> Result<GetPricesResponse, ContractError> {
+        let prices = self.get_nullable_prices(deps, env, pair_ids.clone())?
+            .into_iter()
+            .zip(pair_ids)
+            .map(|(price, pair_id)| price.ok_or_else(|| StdError::not_found(format!("price of {}", pair_id))))
+            .collect::<StdResult<Vec<_>>>()?;
+        Ok(GetPricesResponse { prices })
+    }
+
+    // prices in `pair_ids` order, none for pairs without a price yet
+    pub(crate) fn get_nullable_prices(&self, deps: Deps, env: Env, pair_ids: Vec<String>) -> Result<Vec<Option<GetPriceResponse>>, ContractError> {
+        self.assert_pairs_not_paused(deps.storage, &pair_ids)?;
+
+        let thresholds = self.freshness_thresholds(deps.storage)?;
+        let prices = self.query_prices_raw(deps, pair_ids.clone())?
+            .iter()
+            .zip(pair_ids.iter())
+            .map(|(raw, pair_id)| {
+                let res = convert_raw_price_response(raw)?;
+                if let Some(res) = &res {
+                    self.assert_within_bounds(deps.storage, pair_id, res)?;
+                }
+                Ok(res.map(|res| with_metadata(&env, &thresholds, res)))
+            })
+            .collect::<Result<Vec<_>, ContractError>>()?;
+        Ok(prices)
+    }
+
+    // reports a price or an error per pair instead of failing the whole batch
+    fn get_prices_lenient(&self, deps: Deps, env: Env, pair_ids: Vec<String>) -> Result<GetPricesLenientResponse, ContractError> {
+        let results = self.get_price_results(deps, env, &pair_ids)?;
+        let prices = pair_ids
+            .into_iter()
+            .zip(results)
+            .map(|(pair_id, result)| match result {
+                Ok(Some(price)) => LenientPrice { pair_id, price: Some(price), error: None },
+                Ok(None) => LenientPrice { pair_id, price: None, error: Some("no price reported yet".to_string()) },
+                Err(err) => LenientPrice { pair_id, price: None, error: Some(err.to_string()) },
+            })
+            .collect();
+
+        Ok(GetPricesLenientResponse { prices })
+    }
+
+    // a price, none for pairs without a price yet, or the error of each pair in `pair_ids` order,
+    // so a single paused, removed or malformed pair fails only itself
+    pub(crate) fn get_price_results(
+        &self,
+        deps: Deps,
+        env: Env,
+        pair_ids: &[String],
+    ) -> Result<Vec<Result<Option<GetPriceResponse>, ContractError>>, ContractError> {
+        // pausing a single pair only fails that pair, pausing everything fails the call
+        self.assert_pairs_not_paused(deps.storage, &[])?;
+
+        let queryable: Vec<String> = pair_ids
+            .iter()
+            .filter(|pair_id| self.assert_pairs_not_paused(deps.storage, std::slice::from_ref(pair_id)).is_ok())
+            .cloned()
+            .collect();
+
+        let mut results = match self.query_prices_raw(deps, queryable.clone()) {
+            Ok(raw) if raw.len() == queryable.len() => raw.iter().map(convert_raw_price_response).collect(),
+            // a single failing pair fails the batch, fall back to one query per pair
+            _ => queryable
+                .iter()
+                .map(|pair_id| {
+                    let raw = self.query_prices_raw(deps, vec![pair_id.clone()])?;
+                    let raw = raw.first().ok_or_else(|| StdError::not_found(format!("price of {}", pair_id)))?;
+                    convert_raw_price_response(raw)
+                })
+                .collect::<Vec<_>>(),
+        }
+        .into_iter();
+
+        let thresholds = self.freshness_thresholds(deps.storage)?;
+        Ok(pair_ids
+            .iter()
+            .map(|pair_id| {
+                self.assert_pairs_not_paused(deps.storage, std::slice::from_ref(pair_id))?;
+                let result = results.next().unwrap_or_else(|| Err(StdError::not_found(format!("price of {}", pair_id))))?;
+                if let Some(price) = &result {
+                    self.assert_within_bounds(deps.storage, pair_id, price)?;
+                }
+                Ok(result.map(|price| with_metadata(&env, &thresholds, price)))
+            })
+            .collect())
     }
 
-    fn get_prices(&self, deps: Deps, _env: Env, pair_ids: Vec<String>) -> StdResult<GetPricesResponse> {
+    fn query_prices_raw(&self, deps: Deps, pair_ids: Vec<String>) -> StdResult<Vec<GetPriceResponseRaw>> {
         let request = GetPricesRequest { 
             currency_pair_ids: pair_ids,
             special_fields: ::protobuf::SpecialFields::new()
@@ -32,12 +134,10 @@ impl<'a> Contract {
         let data = Binary::from(bytes);
         let request = QueryRequest::Stargate{path: "/slinky.oracle.v1.Query/GetPrices".to_string(), data};
         let raw_res: GetPricesResponseRaw = deps.querier.query(&request)?;
-        let res = GetPricesResponse {
-            prices: raw_res.prices.into_iter().map(|raw| convert_raw_price_response(&raw)).collect()
-        };
-        Ok(res)
+        Ok(raw_res.prices)
     }
-    fn get_all_currency_pairs(&self, deps: Deps, _env: Env) -> StdResult<GetAllCurrencyPairsResponse> {
+
+    fn get_all_currency_pairs_raw(&self, deps: Deps, _env: Env) -> StdResult<GetAllCurrencyPairsResponseRaw> {
         let request = GetAllCurrencyPairsRequest { 
             special_fields: ::protobuf::SpecialFields::new()
         };
@@ -45,43 +145,348 @@ impl<'a> Contract {
 
         let data = Binary::from(bytes);
         let request = QueryRequest::<Empty>::Stargate{path: "/slinky.oracle.v1.Query/GetAllCurrencyPairs".to_string(), data};
-        let res: GetAllCurrencyPairsResponse = deps.querier.query(&request)?;
+        let res: GetAllCurrencyPairsResponseRaw = deps.querier.query(&request)?;
         Ok(res)
     }
-}
 
+    #[allow(clippy::too_many_arguments)]
+    fn get_all_currency_pairs(
+        &self,
+        deps: Deps,
+        env: Env,
+        start_after: Option<String>,
+        limit: Option<u32>,
+        base: Option<String>,
+        quote: Option<String>,
+        order: Option<SortOrder>,
+    ) -> Result<GetAllCurrencyPairsResponse, ContractError> {
+        let order = order.unwrap_or(SortOrder::Ascending);
+        // filters match tickers in any case, like currency pairs
+        let base = base.as_deref().map(normalize_ticker).transpose()?;
+        let quote = quote.as_deref().map(normalize_ticker).transpose()?;
+        let mut currency_pairs: Vec<CurrencyPairResponse> = self.get_all_currency_pairs_raw(deps, env)?
+            .currency_pairs
+            .into_iter()
+            .filter(|pair| base.as_ref().is_none_or(|base| pair.Base == *base))
+            .filter(|pair| quote.as_ref().is_none_or(|quote| pair.Quote == *quote))
+            .collect();
+
+        currency_pairs.sort_by_key(|pair| pair_id(&pair.Base, &pair.Quote));
+        if order == SortOrder::Descending {
+            currency_pairs.reverse();
+        }
+
+        if let Some(start_after) = start_after {
+            currency_pairs.retain(|pair| {
+                let id = pair_id(&pair.Base, &pair.Quote);
+                match order {
+                    SortOrder::Ascending => id > start_after,
+                    SortOrder::Descending => id < start_after,
+                }
+            });
+        }
+
+        // without a limit every matching pair is returned
+        let mut next_start_after = None;
+        if let Some(limit) = limit.map(|limit| limit as usize) {
+            if currency_pairs.len() > limit {
+                currency_pairs.truncate(limit);
+                next_start_after = currency_pairs.last().map(|pair| pair_id(&pair.Base, &pair.Quote));
+            }
+        }
+
+        Ok(GetAllCurrencyPairsResponse { currency_pairs, next_start_after })
+    }
+
+    pub(crate) fn freshness_thresholds(&self, storage: &dyn Storage) -> StdResult<FreshnessThresholds> {
+        Ok(self.freshness_thresholds.may_load(storage)?.unwrap_or_default())
+    }
+
+    fn get_freshness_thresholds(&self, deps: Deps, _env: Env) -> StdResult<FreshnessThresholds> {
+        self.freshness_thresholds(deps.storage)
+    }
+
+    fn get_all_prices(&self, deps: Deps, env: Env, quote_filter: Option<String>, start_after: Option<String>, limit: Option<u32>) -> Result<GetAllPricesResponse, ContractError> {
+        // pausing a single pair only flags it, pausing everything fails the query
+        self.assert_pairs_not_paused(deps.storage, &[])?;
+
+        let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT);
+        let page = self.get_all_currency_pairs(deps, env.clone(), start_after, Some(limit), None, quote_filter, None)?;
+        let pair_ids: Vec<String> = page.currency_pairs.iter().map(|pair| pair_id(&pair.Base, &pair.Quote)).collect();
+
+        let results = if pair_ids.is_empty() { vec![] } else { self.get_price_results(deps, env, &pair_ids)? };
+        let prices = pair_ids
+            .into_iter()
+            .zip(results)
+            .map(|(pair_id, result)| match result {
+                Ok(price) => PriceTableRow { pair_id, price, error: None },
+                Err(err) => PriceTableRow { pair_id, price: None, error: Some(err.to_string()) },
+            })
+            .collect();
+
+        Ok(GetAllPricesResponse { prices, next_start_after: page.next_start_after })
+    }
+
+    fn get_denom_metadata(&self, deps: Deps, _env: Env, denom: String) -> StdResult<DenomMetadata> {
+        self.denoms.load(deps.storage, &denom)
+    }
+
+    fn convert_amount(&self, deps: Deps, env: Env, amount: Coin, to_denom: String, rounding: RoundingMode) -> Result<ConvertAmountResponse, ContractError> {
+        let config = self.config.load(deps.storage)?;
+        let from = self.denoms.load(deps.storage, &amount.denom)?;
+        let to = self.denoms.load(deps.storage, &to_denom)?;
+
+        // a denom converted into itself needs no price
+        let symbols = if from.symbol == to.symbol { vec![] } else { vec![from.symbol.as_str(), to.symbol.as_str()] };
+        let prices = self.get_quote_prices(deps, env, symbols, &config.quote)?;
+
+        let converted = convert_amount(
+            amount.amount,
+            from.decimals,
+            quote_price_of(&prices, &from.symbol)?,
+            to.decimals,
+            quote_price_of(&prices, &to.symbol)?,
+            rounding,
+        )?;
+
+        Ok(ConvertAmountResponse {
+            amount: Coin { denom: to_denom, amount: converted },
+            prices: prices.into_iter().map(|(_, price)| price).collect(),
+        })
+    }
+
+    fn value_portfolio(&self, deps: Deps, env: Env, coins: Vec<Coin>, quote: String) -> Result<ValuePortfolioResponse, ContractError> {
+        let quote = normalize_ticker(&quote)?;
+        let metadata = coins
+            .iter()
+            .map(|coin| self.denoms.load(deps.storage, &coin.denom))
+            .collect::<StdResult<Vec<_>>>()?;
+
+        let symbols = metadata.iter().map(|m| m.symbol.as_str()).collect();
+        let prices = self.get_quote_prices(deps, env, symbols, &quote)?;
+
+        let mut values = vec![];
+        let mut total_value = Decimal256::zero();
+        for (coin, metadata) in coins.into_iter().zip(metadata) {
+            let value = value_of(coin.amount, metadata.decimals, quote_price_of(&prices, &metadata.symbol)?)?;
+            total_value = total_value.checked_add(value)?;
+            values.push(CoinValue { coin, value });
+        }
+
+        let oldest_timestamp = prices.iter().map(|(_, price)| price.price.block_timestamp).min();
+
+        Ok(ValuePortfolioResponse { values, total_value, oldest_timestamp })
+    }
+
+    fn get_oracle_sources(&self, deps: Deps, _env: Env) -> StdResult<OracleSourcesResponse> {
+        let sources = self.oracle_sources.may_load(deps.storage)?.unwrap_or_else(|| vec![OracleSourceConfig::Slinky {}]);
+        Ok(OracleSourcesResponse { sources })
+    }
+
+    fn get_aggregated_price(&self, deps: Deps, env: Env, pair: CurrencyPair, policy: AggregationPolicy, max_age: Option<u64>) -> Result<AggregatedPriceResponse, ContractError> {
+        // other oracle sources do not go through get_price
+        self.assert_pairs_not_paused(deps.storage, &[pair.to_string()])?;
+        let sources = self.get_oracle_sources(deps, env.clone())?.sources;
+
+        let mut reports = vec![];
+        let mut healthy = vec![];
+        for config in sources.iter() {
+            let source = config.build(self);
+            let report = match source.get_price(deps, env.clone(), pair.clone()) {
+                Ok(res) => source_report(&env, source.name(), res, max_age),
+                Err(err) => SourceReport { source: source.name(), price: None, block_timestamp: None, error: Some(err.to_string()) },
+            };
+            if report.error.is_none() {
+                healthy.extend(report.price);
+            }
+            reports.push(report);
+
+            if matches!(policy, AggregationPolicy::FirstHealthy {}) && !healthy.is_empty() {
+                break;
+            }
+        }
+
+        let price = match policy {
+            AggregationPolicy::FirstHealthy {} => healthy.first().copied(),
+            AggregationPolicy::Median {} => median(healthy)?,
+            AggregationPolicy::MaxDeviation { max_deviation_bps } => {
+                let median = median(healthy.clone())?;
+                if let Some(median) = median {
+                    for price in healthy {
+                        if deviation_bps(price, median)? > Decimal256::from_ratio(max_deviation_bps, 1u64) {
+                            return Err(StdError::generic_err(format!(
+                                "oracle source price {} deviates more than {} bps from median {}", price, max_deviation_bps, median,
+                            )).into());
+                        }
+                    }
+                }
+                median
+            }
+        };
+
+        let price = price.ok_or_else(|| StdError::generic_err(format!("no healthy oracle source for {}", pair)))?;
+        Ok(AggregatedPriceResponse { price, sources: reports })
+    }
+
+    // fetches `{symbol}/{quote}` prices of the given symbols in a single GetPrices call,
+    // skipping duplicates and the quote itself
+    fn get_quote_prices(&self, deps: Deps, env: Env, symbols: Vec<&str>, quote: &str) -> Result<Vec<(String, GetPriceResponse)>, ContractError> {
+        let mut unique: Vec<String> = vec![];
+        for symbol in symbols {
+            if symbol != quote && !unique.iter().any(|s| s == symbol) {
+                unique.push(symbol.to_string());
+            }
+        }
 
-impl<'a> Contract {
-    pub fn query(&self, deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
-        match msg {
-            QueryMsg::GetPrice { base, quote } => to_json_binary(&self.get_price(deps, env, base, quote)?),
-            QueryMsg::GetPrices { pair_ids } => to_json_binary(&self.get_prices(deps, env, pair_ids)?),
-            QueryMsg::GetAllCurrencyPairs {} => to_json_binary(&self.get_all_currency_pairs(deps, env)?),
+        if unique.is_empty() {
+            return Ok(vec![]);
         }
+
+        let pair_ids = unique.iter().map(|symbol| pair_id(symbol, quote)).collect();
+        let prices = self.get_prices(deps, env, pair_ids)?.prices;
+        Ok(unique.into_iter().zip(prices).collect())
+    }
+}
+
+
+impl<'a> Contract<'a> {
+    pub fn query(&self, deps: Deps, env: Env, msg: QueryMsg) -> Result<Binary, ContractError> {
+        self.assert_query_not_paused(deps.storage, msg.kind())?;
+
+        let res = match msg {
+            QueryMsg::GetPrice { pair } => to_json_binary(&self.get_price(deps, env, pair)?),
+            QueryMsg::GetPrices { pair_ids } => to_json_binary(&self.get_prices(deps, env, pair_id_strings(pair_ids))?),
+            QueryMsg::GetAllCurrencyPairs { start_after, limit, base, quote, order }
+                => to_json_binary(&self.get_all_currency_pairs(deps, env, start_after.map(|pair| pair.to_string()), limit, base, quote, order)?),
+            QueryMsg::GetPricesLenient { pair_ids } => to_json_binary(&self.get_prices_lenient(deps, env, pair_id_strings(pair_ids))?),
+            QueryMsg::GetAllPrices { quote_filter, start_after, limit }
+                => to_json_binary(&self.get_all_prices(deps, env, quote_filter, start_after.map(|pair| pair.to_string()), limit)?),
+            QueryMsg::GetDenomMetadata { denom } => to_json_binary(&self.get_denom_metadata(deps, env, denom)?),
+            QueryMsg::ConvertAmount { amount, to_denom, rounding } => to_json_binary(&self.convert_amount(deps, env, amount, to_denom, rounding)?),
+            QueryMsg::ValuePortfolio { coins, quote } => to_json_binary(&self.value_portfolio(deps, env, coins, quote)?),
+            QueryMsg::GetOracleSources {} => to_json_binary(&self.get_oracle_sources(deps, env)?),
+            QueryMsg::GetPairBounds { pair } => to_json_binary(&self.get_pair_bounds(deps, env, pair)?),
+            QueryMsg::GetAggregatedPrice { pair, policy, max_age } => to_json_binary(&self.get_aggregated_price(deps, env, pair, policy, max_age)?),
+            QueryMsg::LatestRoundData { pair } => to_json_binary(&self.latest_round_data(deps, env, pair)?),
+            QueryMsg::GetRoundData { pair, round_id } => to_json_binary(&self.get_round_data(deps, env, pair, round_id)?),
+            QueryMsg::Decimals { pair } => to_json_binary(&self.decimals(deps, env, pair)?),
+            QueryMsg::Description { pair } => to_json_binary(&self.description(deps, env, pair)?),
+            QueryMsg::GetReferenceData { base_symbol, quote_symbol } => to_json_binary(&self.get_reference_data(deps, env, base_symbol, quote_symbol)?),
+            QueryMsg::GetReferenceDataBulk { base_symbols, quote_symbols } => to_json_binary(&self.get_reference_data_bulk(deps, env, base_symbols, quote_symbols)?),
+            QueryMsg::PriceFeed { id } => to_json_binary(&self.price_feed(deps, env, id)?),
+            QueryMsg::GetSubscription { id } => to_json_binary(&self.get_subscription(deps, env, id)?),
+            QueryMsg::GetSubscriptions { start_after, limit } => to_json_binary(&self.get_subscriptions(deps, env, start_after, limit)?),
+            QueryMsg::GetChannelSubscriptions { start_after, limit }
+                => to_json_binary(&self.get_channel_subscriptions(deps, env, start_after, limit)?),
+            QueryMsg::GetKeeper { address } => to_json_binary(&self.get_keeper(deps, env, address)?),
+            QueryMsg::GetKeepers { start_after, limit } => to_json_binary(&self.get_keepers(deps, env, start_after, limit)?),
+            QueryMsg::GetRewardPool {} => to_json_binary(&self.get_reward_pool(deps, env)?),
+            QueryMsg::GetPauseState {} => to_json_binary(&self.get_pause_state(deps, env)?),
+            QueryMsg::GetGuardians {} => to_json_binary(&self.get_guardians(deps, env)?),
+            QueryMsg::GetFreshnessThresholds {} => to_json_binary(&self.get_freshness_thresholds(deps, env)?),
+            QueryMsg::GetOrder { id } => to_json_binary(&self.get_order(deps, env, id)?),
+            QueryMsg::GetOrders { owner, start_after, limit } => to_json_binary(&self.get_orders(deps, env, owner, start_after, limit)?),
+            QueryMsg::GetSettlement { id } => to_json_binary(&self.get_settlement(deps, env, id)?),
+            QueryMsg::GetReceipt { id } => to_json_binary(&self.get_receipt(deps, env, id)?),
+            QueryMsg::GetCumulative { pair } => to_json_binary(&self.get_cumulative(deps, env, pair)?),
+            QueryMsg::GetTwap { pair, window, mode } => to_json_binary(&self.get_twap(deps, env, pair, window, mode)?),
+            QueryMsg::GetEma { pair, half_life } => to_json_binary(&self.get_ema(deps, env, pair, half_life)?),
+            QueryMsg::GetCandleIntervals {} => to_json_binary(&self.get_candle_intervals(deps, env)?),
+            QueryMsg::GetCandles { pair, interval, start_after, limit }
+                => to_json_binary(&self.get_candles(deps, env, pair, interval, start_after, limit)?),
+        };
+        Ok(res?)
+    }
+}
+
+pub(crate) fn pair_id(base: &str, quote: &str) -> String {
+    format!("{}/{}", base, quote)
+}
+
+// canonical {Base}/{Quote} ids of the requested pairs, without duplicates
+fn pair_id_strings(pairs: Vec<CurrencyPair>) -> Vec<String> {
+    dedup_pairs(pairs).iter().map(ToString::to_string).collect()
+}
+
+fn source_report(env: &Env, source: String, res: GetPriceResponse, max_age: Option<u64>) -> SourceReport {
+    let block_timestamp = Some(res.price.block_timestamp);
+    let age = env.block.time.seconds().saturating_sub(res.price.block_timestamp.seconds());
+    if let Some(max_age) = max_age.filter(|max_age| age > *max_age) {
+        let error = format!("price is {} seconds old, max age is {}", age, max_age);
+        return SourceReport { source, price: None, block_timestamp, error: Some(error) };
+    }
+
+    match price_to_decimal(res.price.price, res.decimals) {
+        Ok(price) => SourceReport { source, price: Some(price), block_timestamp, error: None },
+        Err(err) => SourceReport { source, price: None, block_timestamp, error: Some(err.to_string()) },
+    }
+}
+
+// the quote itself is priced at exactly 1
+fn quote_price_of(prices: &[(String, GetPriceResponse)], symbol: &str) -> StdResult<(Uint256, u64)> {
+    match prices.iter().find(|(s, _)| s == symbol) {
+        Some((_, price)) => Ok((unsigned_price(price.price.price)?, price.decimals)),
+        None => Ok((Uint256::one(), 0)),
     }
 }
 
-fn convert_raw_price_response(raw_response: &GetPriceResponseRaw) -> GetPriceResponse {
-    GetPriceResponse {
+fn with_metadata(env: &Env, thresholds: &FreshnessThresholds, mut res: GetPriceResponse) -> GetPriceResponse {
+    let age_seconds = env.block.time.seconds().saturating_sub(res.price.block_timestamp.seconds());
+    let age_blocks = env.block.height.saturating_sub(res.price.block_height);
+
+    let freshness = if age_seconds <= thresholds.fresh_seconds {
+        Freshness::Fresh
+    } else if age_seconds <= thresholds.stale_seconds {
+        Freshness::Stale
+    } else {
+        Freshness::Expired
+    };
+
+    res.metadata = Some(PriceMetadata {
+        age_seconds,
+        age_blocks,
+        freshness,
+        // every price update advances the nonce, so the last update height tells when it last advanced
+        nonce_advanced: res.nonce > 0 && age_blocks <= thresholds.nonce_window_blocks,
+    });
+    res
+}
+
+// none when no price was reported yet
+fn convert_raw_price_response(raw_response: &GetPriceResponseRaw) -> StdResult<Option<GetPriceResponse>> {
+    let price = match raw_response.price.as_ref() {
+        Some(price) => price,
+        None => return Ok(None),
+    };
+    Ok(Some(GetPriceResponse {
         price: QuotePrice {
-            price: Uint256::from_str(&raw_response.price.price).unwrap(),
-            block_timestamp: convert_iso_string_to_timestamp(&raw_response.price.block_timestamp),
-            block_height: u64::from_str(&raw_response.price.block_height).unwrap(),
+            price: parse_raw("price", &price.price)?,
+            block_timestamp: price.block_timestamp,
+            block_height: price.block_height,
         },
-        nonce: u64::from_str(&raw_response.nonce).unwrap(),
-        decimals: u64::from_str(&raw_response.decimals).unwrap(),
-        id: u64::from_str(&raw_response.id).unwrap(),
-    }
+        nonce: raw_response.nonce,
+        decimals: raw_response.decimals,
+        id: raw_response.id,
+        metadata: None,
+    }))
+}
+
+fn parse_raw<T: FromStr>(field: &str, value: &str) -> StdResult<T> {
+    T::from_str(value).map_err(|_| StdError::parse_err(field, format!("invalid value {}", value)))
 }
 
 // stargate query responses
 
 #[derive(Clone, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
 pub struct GetPriceResponseRaw {
-    pub price: QuotePriceRaw,
-    pub nonce: String,
-    pub decimals: String,
-    pub id: String,
+    #[serde(default)]
+    pub price: Option<QuotePriceRaw>,
+    #[serde(deserialize_with = "deserialize_u64")]
+    pub nonce: u64,
+    #[serde(deserialize_with = "deserialize_u64")]
+    pub decimals: u64,
+    #[serde(deserialize_with = "deserialize_u64")]
+    pub id: u64,
 }
 
 #[derive(Clone, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
@@ -93,8 +498,10 @@ pub struct GetPricesResponseRaw {
 #[derive(Clone, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
 pub struct QuotePriceRaw {
     pub price: String,
-    pub block_timestamp: String,
-    pub block_height: String,
+    #[serde(deserialize_with = "deserialize_timestamp")]
+    pub block_timestamp: Timestamp,
+    #[serde(deserialize_with = "deserialize_u64")]
+    pub block_height: u64,
 }
 
 // query response
@@ -105,6 +512,28 @@ pub struct GetPriceResponse {
     pub nonce: u64,
     pub decimals: u64,
     pub id: u64,
+    /// computed against the querying block, absent in responses of other price feed contracts
+    #[serde(default)]
+    pub metadata: Option<PriceMetadata>,
+}
+
+#[derive(Clone, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct PriceMetadata {
+    pub age_seconds: u64,
+    pub age_blocks: u64,
+    pub freshness: Freshness,
+    /// whether the nonce advanced within the last `nonce_window_blocks` blocks
+    pub nonce_advanced: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Freshness {
+    /// at most `fresh_seconds` old
+    Fresh,
+    /// at most `stale_seconds` old
+    Stale,
+    Expired,
 }
 
 #[derive(Clone, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
@@ -114,18 +543,374 @@ pub struct GetPricesResponse {
 
 #[derive(Clone, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
 pub struct QuotePrice {
-    pub price: Uint256,
+    /// cosmos.Int, negative for e.g. spreads or funding rates
+    pub price: Int256,
     pub block_timestamp: Timestamp,
     pub block_height: u64,
 }
 
+#[derive(Clone, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct GetAllCurrencyPairsResponseRaw {
+    pub currency_pairs: Vec<CurrencyPairResponse>,
+}
+
 #[derive(Clone, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
 pub struct GetAllCurrencyPairsResponse {
     pub currency_pairs: Vec<CurrencyPairResponse>,
+    /// {Base}/{Quote} to continue from, none on the last page
+    pub next_start_after: Option<String>,
 }
 #[derive(Clone, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
 #[allow(non_snake_case)]
 pub struct CurrencyPairResponse {
     pub Base: String,
     pub Quote: String,
-}
\ No newline at end of file
+}
+
+#[derive(Clone, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct GetPricesLenientResponse {
+    pub prices: Vec<LenientPrice>,
+}
+
+#[derive(Clone, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct LenientPrice {
+    pub pair_id: String,
+    pub price: Option<GetPriceResponse>,
+    /// set when the price is missing
+    pub error: Option<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct GetAllPricesResponse {
+    pub prices: Vec<PriceTableRow>,
+    /// {Base}/{Quote} to continue from, none on the last page
+    pub next_start_after: Option<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct PriceTableRow {
+    pub pair_id: String,
+    /// none when the pair has no price yet or could not be queried
+    pub price: Option<GetPriceResponse>,
+    /// why the pair could not be queried, none for pairs without a price yet
+    pub error: Option<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct ConvertAmountResponse {
+    pub amount: Coin,
+    /// prices used for the conversion, in `{symbol}/{quote}` form
+    pub prices: Vec<GetPriceResponse>,
+}
+
+#[derive(Clone, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct ValuePortfolioResponse {
+    pub values: Vec<CoinValue>,
+    pub total_value: Decimal256,
+    /// oldest price timestamp used, none if no price was needed
+    pub oldest_timestamp: Option<Timestamp>,
+}
+
+#[derive(Clone, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct CoinValue {
+    pub coin: Coin,
+    /// value in whole units of the quote
+    pub value: Decimal256,
+}
+
+#[derive(Clone, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct OracleSourcesResponse {
+    pub sources: Vec<OracleSourceConfig>,
+}
+
+#[derive(Clone, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct AggregatedPriceResponse {
+    pub price: SignedDecimal256,
+    /// per source outcome, in configured order
+    pub sources: Vec<SourceReport>,
+}
+
+#[derive(Clone, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct SourceReport {
+    pub source: String,
+    pub price: Option<SignedDecimal256>,
+    pub block_timestamp: Option<Timestamp>,
+    /// set when the source failed or is unhealthy
+    pub error: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use cosmwasm_std::{coin, Uint128};
+
+    use super::*;
+    use crate::msgs::ExecuteMsg;
+    use crate::testing::{error_of, Suite, ADMIN, USER};
+
+    fn register_denom(suite: &mut Suite, denom: &str, symbol: &str, decimals: u32) {
+        let msg = ExecuteMsg::RegisterDenom { denom: denom.to_string(), symbol: symbol.to_string(), decimals };
+        suite.execute(ADMIN, &msg, &[]).unwrap();
+    }
+
+    fn convert(suite: &Suite, amount: u128, from: &str, to: &str, rounding: RoundingMode) -> StdResult<ConvertAmountResponse> {
+        suite.query(&QueryMsg::ConvertAmount { amount: coin(amount, from), to_denom: to.to_string(), rounding })
+    }
+
+    #[test]
+    fn convert_amount_between_denoms() {
+        let mut suite = Suite::new();
+        register_denom(&mut suite, "uinit", "INIT", 6);
+        register_denom(&mut suite, "wei", "ETH", 18);
+        suite.set_price("INIT/USD", 150_000_000, 8);
+        suite.set_price("ETH/USD", 300_000_000_000, 8);
+
+        // 2 INIT at $1.5 is $3, 0.001 ETH
+        let res = convert(&suite, 2_000_000, "uinit", "wei", RoundingMode::Floor).unwrap();
+        assert_eq!(res.amount, coin(1_000_000_000_000_000, "wei"));
+        assert_eq!(res.prices.len(), 2);
+
+        let res = convert(&suite, 1_000_000_000_000_000, "wei", "uinit", RoundingMode::Floor).unwrap();
+        assert_eq!(res.amount, coin(2_000_000, "uinit"));
+    }
+
+    #[test]
+    fn convert_amount_rounding() {
+        let mut suite = Suite::new();
+        register_denom(&mut suite, "uinit", "INIT", 6);
+        register_denom(&mut suite, "uatom", "ATOM", 6);
+        suite.set_price("INIT/USD", 1, 0);
+        suite.set_price("ATOM/USD", 3, 0);
+
+        // 10 uinit is worth 3.33.. uatom
+        let amount = |rounding| convert(&suite, 10, "uinit", "uatom", rounding).unwrap().amount.amount;
+        assert_eq!(amount(RoundingMode::Floor), Uint128::new(3));
+        assert_eq!(amount(RoundingMode::Ceil), Uint128::new(4));
+        assert_eq!(amount(RoundingMode::HalfUp), Uint128::new(3));
+        let res = convert(&suite, 5, "uatom", "uinit", RoundingMode::HalfUp).unwrap();
+        assert_eq!(res.amount.amount, Uint128::new(15));
+    }
+
+    #[test]
+    fn convert_amount_without_prices() {
+        let mut suite = Suite::new();
+        register_denom(&mut suite, "uusdc", "USD", 6);
+        register_denom(&mut suite, "ibc/usdc", "USD", 6);
+        register_denom(&mut suite, "uinit", "INIT", 6);
+        suite.set_price("INIT/USD", 2, 0);
+
+        // same symbol needs no price
+        let res = convert(&suite, 42, "uusdc", "ibc/usdc", RoundingMode::Floor).unwrap();
+        assert_eq!(res.amount, coin(42, "ibc/usdc"));
+        assert!(res.prices.is_empty());
+
+        // the quote itself is priced at 1
+        let res = convert(&suite, 3, "uinit", "uusdc", RoundingMode::Floor).unwrap();
+        assert_eq!(res.amount, coin(6, "uusdc"));
+        assert_eq!(res.prices.len(), 1);
+    }
+
+    #[test]
+    fn convert_amount_errors() {
+        let mut suite = Suite::new();
+        register_denom(&mut suite, "uinit", "INIT", 6);
+        register_denom(&mut suite, "uatom", "ATOM", 6);
+        suite.set_price("INIT/USD", 2, 0);
+
+        // unregistered denom
+        assert!(convert(&suite, 1, "uinit", "unknown", RoundingMode::Floor).is_err());
+        // pair missing from the oracle
+        assert!(convert(&suite, 1, "uinit", "uatom", RoundingMode::Floor).is_err());
+
+        let msg = ExecuteMsg::RegisterDenom { denom: "uosmo".to_string(), symbol: "OSMO".to_string(), decimals: 6 };
+        let err = suite.execute(USER, &msg, &[]).unwrap_err();
+        assert_eq!(error_of(err), ContractError::Unauthorized {}.to_string());
+    }
+
+    #[test]
+    fn value_portfolio_in_quote() {
+        let mut suite = Suite::new();
+        register_denom(&mut suite, "uinit", "INIT", 6);
+        register_denom(&mut suite, "wei", "ETH", 18);
+        register_denom(&mut suite, "uusdc", "USD", 6);
+        suite.set_price("INIT/USD", 150_000_000, 8);
+        suite.set_price("ETH/USD", 3_000_000, 3);
+
+        let coins = vec![coin(2_000_000, "uinit"), coin(500_000_000_000_000_000, "wei"), coin(250_000, "uusdc")];
+        let res: ValuePortfolioResponse = suite.query(&QueryMsg::ValuePortfolio { coins: coins.clone(), quote: "USD".to_string() }).unwrap();
+        let values: Vec<_> = res.values.iter().map(|value| value.value.to_string()).collect();
+        assert_eq!(values, vec!["3", "1500", "0.25"]);
+        assert_eq!(res.values[0].coin, coins[0]);
+        assert_eq!(res.total_value.to_string(), "1503.25");
+        assert_eq!(res.oldest_timestamp, Some(suite.app.block_info().time));
+
+        // a portfolio of the quote needs no price
+        let res: ValuePortfolioResponse = suite.query(&QueryMsg::ValuePortfolio { coins: vec![coin(1_000_000, "uusdc")], quote: "USD".to_string() }).unwrap();
+        assert_eq!(res.total_value, Decimal256::one());
+        assert_eq!(res.oldest_timestamp, None);
+
+        // every coin must be registered
+        let res: StdResult<ValuePortfolioResponse> = suite.query(&QueryMsg::ValuePortfolio { coins: vec![coin(1, "unknown")], quote: "USD".to_string() });
+        assert!(res.is_err());
+    }
+
+    fn metadata(suite: &Suite) -> PriceMetadata {
+        let res: GetPriceResponse = suite.query(&QueryMsg::GetPrice { pair: "BTC/USD".parse().unwrap() }).unwrap();
+        res.metadata.unwrap()
+    }
+
+    #[test]
+    fn price_metadata_grades_freshness() {
+        let mut suite = Suite::new();
+        suite.set_price("BTC/USD", 100, 0);
+        assert_eq!(metadata(&suite), PriceMetadata { age_seconds: 0, age_blocks: 0, freshness: Freshness::Fresh, nonce_advanced: true });
+
+        suite.advance(30);
+        assert_eq!(metadata(&suite).freshness, Freshness::Fresh);
+        suite.advance(1);
+        assert_eq!(metadata(&suite), PriceMetadata { age_seconds: 31, age_blocks: 2, freshness: Freshness::Stale, nonce_advanced: true });
+        suite.advance(270);
+        assert_eq!(metadata(&suite).freshness, Freshness::Expired);
+
+        // the nonce advanced in the last 10 blocks
+        for _ in 0..8 {
+            suite.advance(1);
+        }
+        assert_eq!(metadata(&suite).age_blocks, 11);
+        assert!(!metadata(&suite).nonce_advanced);
+        suite.set_price("BTC/USD", 101, 0);
+        assert!(metadata(&suite).nonce_advanced);
+    }
+
+    #[test]
+    fn freshness_thresholds_are_configurable() {
+        let mut suite = Suite::new();
+        suite.set_price("BTC/USD", 100, 0);
+        let thresholds = FreshnessThresholds { fresh_seconds: 10, stale_seconds: 5, nonce_window_blocks: 1 };
+        let err = suite.execute(ADMIN, &ExecuteMsg::SetFreshnessThresholds { thresholds: thresholds.clone() }, &[]).unwrap_err();
+        assert_eq!(error_of(err), "fresh_seconds must not exceed stale_seconds");
+
+        let thresholds = FreshnessThresholds { fresh_seconds: 5, stale_seconds: 10, nonce_window_blocks: 1 };
+        let err = suite.execute(USER, &ExecuteMsg::SetFreshnessThresholds { thresholds: thresholds.clone() }, &[]).unwrap_err();
+        assert_eq!(error_of(err), "Unauthorized");
+        suite.sudo(&crate::msgs::SudoMsg::SetFreshnessThresholds { thresholds: thresholds.clone() }).unwrap();
+        assert_eq!(suite.query::<FreshnessThresholds>(&QueryMsg::GetFreshnessThresholds {}).unwrap(), thresholds);
+
+        suite.advance(6);
+        assert_eq!(metadata(&suite), PriceMetadata { age_seconds: 6, age_blocks: 1, freshness: Freshness::Stale, nonce_advanced: true });
+        suite.advance(5);
+        assert_eq!(metadata(&suite), PriceMetadata { age_seconds: 11, age_blocks: 2, freshness: Freshness::Expired, nonce_advanced: false });
+    }
+
+    fn currency_pairs(suite: &Suite, start_after: Option<&str>, limit: Option<u32>, base: Option<&str>, quote: Option<&str>, order: Option<SortOrder>) -> (Vec<String>, Option<String>) {
+        let res: GetAllCurrencyPairsResponse = suite.query(&QueryMsg::GetAllCurrencyPairs {
+            start_after: start_after.map(|pair| pair.parse().unwrap()),
+            limit,
+            base: base.map(ToString::to_string),
+            quote: quote.map(ToString::to_string),
+            order,
+        }).unwrap();
+        (res.currency_pairs.iter().map(|pair| pair_id(&pair.Base, &pair.Quote)).collect(), res.next_start_after)
+    }
+
+    #[test]
+    fn get_all_currency_pairs_pages_and_filters() {
+        let suite = Suite::new();
+        for pair_id in ["ETH/USD", "BTC/USD", "BTC/EUR", "ATOM/USD"] {
+            suite.slinky.add_pair(pair_id, 8);
+        }
+
+        let (pairs, next) = currency_pairs(&suite, None, Some(2), None, None, None);
+        assert_eq!((pairs, next.clone()), (vec!["ATOM/USD".to_string(), "BTC/EUR".to_string()], Some("BTC/EUR".to_string())));
+        let (pairs, next) = currency_pairs(&suite, next.as_deref(), Some(2), None, None, None);
+        assert_eq!((pairs, next), (vec!["BTC/USD".to_string(), "ETH/USD".to_string()], None));
+
+        let (pairs, _) = currency_pairs(&suite, None, None, None, None, Some(SortOrder::Descending));
+        assert_eq!(pairs, vec!["ETH/USD", "BTC/USD", "BTC/EUR", "ATOM/USD"]);
+        let (pairs, _) = currency_pairs(&suite, Some("btc/usd"), None, None, None, Some(SortOrder::Descending));
+        assert_eq!(pairs, vec!["BTC/EUR", "ATOM/USD"]);
+
+        // filters are normalized like tickers
+        let (pairs, _) = currency_pairs(&suite, None, None, Some(" btc"), None, None);
+        assert_eq!(pairs, vec!["BTC/EUR", "BTC/USD"]);
+        let (pairs, _) = currency_pairs(&suite, None, None, Some("btc"), Some("usd"), None);
+        assert_eq!(pairs, vec!["BTC/USD"]);
+        let res: StdResult<GetAllCurrencyPairsResponse> = suite.query(&QueryMsg::GetAllCurrencyPairs {
+            start_after: None, limit: None, base: None, quote: Some("US D".to_string()), order: None,
+        });
+        assert!(res.unwrap_err().to_string().contains("Invalid currency pair US D"));
+    }
+
+    #[test]
+    fn get_all_prices_flags_missing_and_failing_prices() {
+        let mut suite = Suite::new();
+        suite.set_price("BTC/USD", 100, 0);
+        suite.set_price("ETH/USD", 10, 0);
+        suite.slinky.add_pair("NEW/USD", 8);
+        suite.set_price("BTC/EUR", 90, 0);
+        let target = crate::msgs::PauseTarget::Pair { pair_id: "ETH/USD".parse().unwrap() };
+        suite.execute(ADMIN, &ExecuteMsg::Pause { target }, &[]).unwrap();
+
+        let res: GetAllPricesResponse = suite.query(&QueryMsg::GetAllPrices { quote_filter: Some("usd".to_string()), start_after: None, limit: Some(2) }).unwrap();
+        assert_eq!(res.prices.len(), 2);
+        assert_eq!((res.prices[0].pair_id.as_str(), res.prices[0].error.as_deref()), ("BTC/USD", None));
+        assert!(res.prices[0].price.is_some());
+        assert_eq!(res.prices[1].pair_id, "ETH/USD");
+        assert_eq!((res.prices[1].price.as_ref(), res.prices[1].error.as_deref()), (None, Some("Paused: pair ETH/USD")));
+
+        let start_after = res.next_start_after.map(|pair| pair.parse().unwrap());
+        let res: GetAllPricesResponse = suite.query(&QueryMsg::GetAllPrices { quote_filter: Some("USD".to_string()), start_after, limit: Some(2) }).unwrap();
+        assert_eq!(res.prices, vec![PriceTableRow { pair_id: "NEW/USD".to_string(), price: None, error: None }]);
+        assert_eq!(res.next_start_after, None);
+    }
+
+    #[test]
+    fn get_prices_lenient_reports_errors_per_pair() {
+        let mut suite = Suite::new();
+        suite.set_price("BTC/USD", 100, 0);
+        suite.set_price("ETH/USD", 10, 0);
+        suite.slinky.add_pair("NEW/USD", 8);
+        let target = crate::msgs::PauseTarget::Pair { pair_id: "ETH/USD".parse().unwrap() };
+        suite.execute(ADMIN, &ExecuteMsg::Pause { target }, &[]).unwrap();
+
+        let pair_ids = ["BTC/USD", "SOL/USD", "NEW/USD", "ETH/USD", "btc/usd"].iter().map(|pair| pair.parse().unwrap()).collect();
+        let res: GetPricesLenientResponse = suite.query(&QueryMsg::GetPricesLenient { pair_ids }).unwrap();
+        let rows: Vec<(&str, bool, Option<&str>)> = res.prices
+            .iter()
+            .map(|row| (row.pair_id.as_str(), row.price.is_some(), row.error.as_deref()))
+            .collect();
+        assert_eq!(rows[0], ("BTC/USD", true, None));
+        assert_eq!((rows[1].0, rows[1].1), ("SOL/USD", false));
+        assert!(rows[1].2.unwrap().contains("no price for currency pair SOL/USD"));
+        assert_eq!(rows[2], ("NEW/USD", false, Some("no price reported yet")));
+        assert_eq!(rows[3], ("ETH/USD", false, Some("Paused: pair ETH/USD")));
+        assert_eq!(rows.len(), 4);
+
+        // the strict query fails as a whole
+        let strict: StdResult<GetPricesResponse> = suite.query(&QueryMsg::GetPrices { pair_ids: vec!["BTC/USD".parse().unwrap(), "SOL/USD".parse().unwrap()] });
+        assert!(strict.is_err());
+    }
+
+    #[test]
+    fn negative_prices() {
+        let mut suite = Suite::new();
+        suite.set_price("BTC-PERP/FUNDING", -125, 6);
+        let pair: CurrencyPair = "BTC-PERP/FUNDING".parse().unwrap();
+        let res: GetPriceResponse = suite.query(&QueryMsg::GetPrice { pair: pair.clone() }).unwrap();
+        assert_eq!(res.price.price, Int256::from(-125i64));
+        let res: GetPricesResponse = suite.query(&QueryMsg::GetPrices { pair_ids: vec![pair.clone()] }).unwrap();
+        assert_eq!(res.prices[0].price.price, Int256::from(-125i64));
+
+        suite.execute(USER, &ExecuteMsg::QuoteAndRecord { pairs: vec![pair.clone()] }, &[]).unwrap();
+        suite.advance(10);
+        let res: crate::cumulative::CumulativeResponse = suite.query(&QueryMsg::GetCumulative { pair }).unwrap();
+        assert_eq!(res.price, SignedDecimal256::from_str("-0.000125").unwrap());
+        assert_eq!(res.cumulative, SignedDecimal256::from_str("-0.00125").unwrap());
+
+        // token amounts cannot be converted at a negative price
+        register_denom(&mut suite, "ufund", "BTC-PERP", 6);
+        register_denom(&mut suite, "uquote", "FUNDING", 6);
+        suite.sudo(&crate::msgs::SudoMsg::SetConfig { admin: None, quote: Some("FUNDING".to_string()) }).unwrap();
+        let err = convert(&suite, 1, "ufund", "uquote", RoundingMode::Floor).unwrap_err();
+        assert!(err.to_string().contains("negative price -125 is not supported here"), "{}", err);
+    }
+}
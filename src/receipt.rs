@@ -0,0 +1,175 @@
+use cosmwasm_std::{to_json_binary, Addr, Deps, DepsMut, Env, MessageInfo, Response, Timestamp};
+use serde::{Deserialize, Serialize};
+
+use crate::currency_pair::{dedup_pairs, CurrencyPair};
+use crate::error::ContractError;
+use crate::events::price_events;
+use crate::state::Contract;
+use crate::subscription::PairPrice;
+
+/// Snapshot of the prices returned to a `QuoteAndRecord` caller.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct Receipt {
+    pub id: u64,
+    pub requester: Addr,
+    pub block_height: u64,
+    pub block_time: Timestamp,
+    pub prices: Vec<PairPrice>,
+}
+
+/// Set as the response data of `QuoteAndRecord`.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct QuoteAndRecordResponse {
+    pub receipt_id: u64,
+    pub prices: Vec<PairPrice>,
+}
+
+impl<'a> Contract<'a> {
+    pub(crate) fn quote_and_record(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        pairs: Vec<CurrencyPair>,
+    ) -> Result<Response, ContractError> {
+        if pairs.is_empty() {
+            return Err(ContractError::EmptyPairs {});
+        }
+        let pair_ids: Vec<String> = dedup_pairs(pairs).iter().map(ToString::to_string).collect();
+        let prices = self.get_prices(deps.as_ref(), env.clone(), pair_ids.clone())?.prices;
+        let recorded = self.record_prices(deps.storage, &env, &info.sender, &pair_ids, &prices)?;
+
+        let id = self.receipt_count.may_load(deps.storage)?.unwrap_or_default() + 1;
+        self.receipt_count.save(deps.storage, &id)?;
+        let receipt = Receipt {
+            id,
+            requester: info.sender,
+            block_height: env.block.height,
+            block_time: env.block.time,
+            prices: pair_ids
+                .iter()
+                .cloned()
+                .zip(prices.iter().cloned())
+                .map(|(pair_id, price)| PairPrice { pair_id, price })
+                .collect(),
+        };
+        self.receipts.save(deps.storage, id, &receipt)?;
+
+        let data = QuoteAndRecordResponse { receipt_id: id, prices: receipt.prices };
+        Ok(Response::new()
+            .add_attribute("action", "quote_and_record")
+            .add_attribute("receipt_id", id.to_string())
+            .add_attribute("requester", receipt.requester)
+            .add_attribute("useful", recorded.keeper.useful.to_string())
+            .add_attributes((!recorded.deferred_settlements.is_empty()).then(|| ("deferred_settlements", recorded.deferred_settlements.join(","))))
+            .add_events(price_events("quote_and_record", &pair_ids, &prices))
+            .add_messages(recorded.keeper.payout)
+            .set_data(to_json_binary(&data)?))
+    }
+
+    pub(crate) fn get_receipt(&self, deps: Deps, _env: Env, id: u64) -> Result<Receipt, ContractError> {
+        let receipt = self.receipts.load(deps.storage, id)?;
+        let pair_ids: Vec<String> = receipt.prices.iter().map(|price| price.pair_id.clone()).collect();
+        self.assert_pairs_not_paused(deps.storage, &pair_ids)?;
+        Ok(receipt)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cosmwasm_std::{from_json, Empty, Int256, Reply, StdError, StdResult, SubMsg, WasmMsg};
+    use cw_multi_test::{ContractWrapper, Executor};
+    use cw_storage_plus::Item;
+    use protobuf::CodedInputStream;
+
+    use super::*;
+    use crate::msgs::{ExecuteMsg, QueryMsg};
+    use crate::testing::{attribute, error_of, Suite, ADMIN, USER};
+
+    fn quote_and_record(suite: &mut Suite, pairs: &[&str]) -> anyhow::Result<cw_multi_test::AppResponse> {
+        let pairs = pairs.iter().map(|pair| pair.parse().unwrap()).collect();
+        suite.execute(USER, &ExecuteMsg::QuoteAndRecord { pairs }, &[])
+    }
+
+    #[test]
+    fn receipts_snapshot_the_returned_prices() {
+        let mut suite = Suite::new();
+        suite.set_price("BTC/USD", 100, 0);
+        suite.set_price("ETH/USD", 10, 0);
+
+        let res = quote_and_record(&mut suite, &["BTC/USD", "eth/usd", "BTC/USD"]).unwrap();
+        let data: QuoteAndRecordResponse = from_json(res.data.as_ref().unwrap()).unwrap();
+        assert_eq!(data.receipt_id, 1);
+        assert_eq!(attribute(&res, "receipt_id"), "1");
+        let pair_ids: Vec<_> = data.prices.iter().map(|price| price.pair_id.as_str()).collect();
+        assert_eq!(pair_ids, vec!["BTC/USD", "ETH/USD"]);
+
+        // later prices leave the receipt untouched
+        suite.advance(5);
+        suite.set_price("BTC/USD", 200, 0);
+        let receipt: Receipt = suite.query(&QueryMsg::GetReceipt { id: 1 }).unwrap();
+        assert_eq!(receipt.requester, Addr::unchecked(USER));
+        assert_eq!(receipt.prices, data.prices);
+        assert_eq!(receipt.prices[0].price.price.price, Int256::from(100));
+
+        let data: QuoteAndRecordResponse = from_json(quote_and_record(&mut suite, &["BTC/USD"]).unwrap().data.unwrap()).unwrap();
+        assert_eq!(data.receipt_id, 2);
+        assert_eq!(data.prices[0].price.price.price, Int256::from(200));
+        assert!(suite.query::<Receipt>(&QueryMsg::GetReceipt { id: 3 }).is_err());
+    }
+
+    #[test]
+    fn failed_quotes_store_no_receipt() {
+        let mut suite = Suite::new();
+        suite.set_price("BTC/USD", 100, 0);
+        assert_eq!(error_of(quote_and_record(&mut suite, &[]).unwrap_err()), "At least one currency pair is required");
+        quote_and_record(&mut suite, &["BTC/USD", "ETH/USD"]).unwrap_err();
+        assert!(suite.query::<Receipt>(&QueryMsg::GetReceipt { id: 1 }).is_err());
+
+        let data: QuoteAndRecordResponse = from_json(quote_and_record(&mut suite, &["BTC/USD"]).unwrap().data.unwrap()).unwrap();
+        assert_eq!(data.receipt_id, 1);
+    }
+
+    #[test]
+    fn consumed_in_a_submessage_reply() {
+        // a consumer storing the receipt id it was quoted in its reply
+        const ORACLE: Item<Addr> = Item::new("oracle");
+        const QUOTE: Item<QuoteAndRecordResponse> = Item::new("quote");
+        let code = ContractWrapper::new(
+            |deps: DepsMut, _: Env, _: MessageInfo, _: Empty| -> StdResult<Response> {
+                let msg = WasmMsg::Execute {
+                    contract_addr: ORACLE.load(deps.storage)?.to_string(),
+                    msg: to_json_binary(&ExecuteMsg::QuoteAndRecord { pairs: vec!["BTC/USD".parse().unwrap()] })?,
+                    funds: vec![],
+                };
+                Ok(Response::new().add_submessage(SubMsg::reply_on_success(msg, 1)))
+            },
+            |deps: DepsMut, _: Env, _: MessageInfo, oracle: Addr| -> StdResult<Response> {
+                ORACLE.save(deps.storage, &oracle)?;
+                Ok(Response::new())
+            },
+            |deps: Deps, _: Env, _: Empty| -> StdResult<cosmwasm_std::Binary> { to_json_binary(&QUOTE.load(deps.storage)?) },
+        )
+        .with_reply(|deps: DepsMut, _: Env, reply: Reply| -> StdResult<Response> {
+            // MsgExecuteContractResponse { data = 1 }
+            let response = reply.result.unwrap().data.unwrap();
+            let mut input = CodedInputStream::from_bytes(response.as_slice());
+            input.read_raw_tag_or_eof().unwrap();
+            let data = input.read_bytes().map_err(|err| StdError::generic_err(err.to_string()))?;
+            QUOTE.save(deps.storage, &from_json(data)?)?;
+            Ok(Response::new())
+        });
+
+        let mut suite = Suite::new();
+        suite.set_price("BTC/USD", 100, 0);
+        let code_id = suite.app.store_code(Box::new(code));
+        let oracle = suite.contract.clone();
+        let consumer = suite.app.instantiate_contract(code_id, Addr::unchecked(ADMIN), &oracle, &[], "consumer", None).unwrap();
+        suite.app.execute_contract(Addr::unchecked(USER), consumer.clone(), &Empty {}, &[]).unwrap();
+
+        let quote: QuoteAndRecordResponse = suite.app.wrap().query_wasm_smart(&consumer, &Empty {}).unwrap();
+        let receipt: Receipt = suite.query(&QueryMsg::GetReceipt { id: quote.receipt_id }).unwrap();
+        assert_eq!(receipt.requester, consumer);
+        assert_eq!(receipt.prices, quote.prices);
+    }
+}
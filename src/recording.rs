@@ -0,0 +1,38 @@
+use cosmwasm_std::{Addr, Env, StdResult, Storage};
+
+use crate::keeper::KeeperUpdate;
+use crate::query::GetPriceResponse;
+use crate::state::Contract;
+
+/// Outcome of recording prices.
+pub struct Recorded {
+    pub keeper: KeeperUpdate,
+    /// pairs whose due settlements were not all settled, beyond the per record bound
+    pub deferred_settlements: Vec<String>,
+}
+
+impl<'a> Contract<'a> {
+    // called by every execute that reads prices on behalf of a caller, feeds the
+    // on-chain price history and credits the caller as a keeper
+    pub(crate) fn record_prices(
+        &self,
+        storage: &mut dyn Storage,
+        env: &Env,
+        keeper: &Addr,
+        pair_ids: &[String],
+        prices: &[GetPriceResponse],
+    ) -> StdResult<Recorded> {
+        let useful = self.advance_nonces(storage, pair_ids, prices)?;
+        let payout = self.reward_keeper(storage, keeper, useful)?;
+        let mut deferred_settlements = vec![];
+        for (pair_id, price) in pair_ids.iter().zip(prices.iter()) {
+            self.accumulate(storage, env, pair_id, price)?;
+            self.observe(storage, env, pair_id, price)?;
+            self.update_candles(storage, env, pair_id, price)?;
+            if self.settle_due(storage, env, pair_id, price)? {
+                deferred_settlements.push(pair_id.clone());
+            }
+        }
+        Ok(Recorded { keeper: KeeperUpdate { useful, payout }, deferred_settlements })
+    }
+}
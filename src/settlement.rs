@@ -0,0 +1,319 @@
+use cosmwasm_std::{Addr, Deps, DepsMut, Env, MessageInfo, Order, Response, StdResult, Storage, Timestamp};
+use cw_storage_plus::Bound;
+use serde::{Deserialize, Serialize};
+
+use crate::currency_pair::CurrencyPair;
+use crate::error::ContractError;
+use crate::events::price_event;
+use crate::query::GetPriceResponse;
+use crate::state::Contract;
+
+/// seconds after `settle_at` a price is accepted for when not set
+pub const DEFAULT_SETTLEMENT_DELAY: u64 = 10;
+/// slinky only reports its latest price, so whoever records first may pick any price published
+/// within `max_delay`, which is kept to a few blocks
+pub const MAX_SETTLEMENT_DELAY: u64 = 60;
+/// due settlements of a pair settled per recorded price, the rest by later records or `finalize_settlement`
+const MAX_SETTLEMENTS_PER_RECORD: usize = 10;
+
+/// Price of a pair at a target time, written once by the first recorded price at or after it.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct Settlement {
+    pub id: u64,
+    pub owner: Addr,
+    /// {Base}/{Quote}
+    pub pair: String,
+    pub settle_at: Timestamp,
+    /// seconds after `settle_at` the settlement price may be from
+    pub max_delay: u64,
+    pub status: SettlementStatus,
+    /// first recorded price with `settle_at <= block_timestamp <= settle_at + max_delay`
+    pub price: Option<GetPriceResponse>,
+    /// block time of the finalization
+    pub finalized_at: Option<Timestamp>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum SettlementStatus {
+    Pending,
+    Finalized,
+    /// no price within `max_delay` of `settle_at` was recorded
+    Missed,
+}
+
+impl std::fmt::Display for SettlementStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SettlementStatus::Pending => write!(f, "pending"),
+            SettlementStatus::Finalized => write!(f, "finalized"),
+            SettlementStatus::Missed => write!(f, "missed"),
+        }
+    }
+}
+
+impl Settlement {
+    fn deadline(&self) -> Timestamp {
+        self.settle_at.plus_seconds(self.max_delay)
+    }
+
+    // settles on `price` if it is the one to settle on, true when no longer pending
+    fn settle(&mut self, env: &Env, price: &GetPriceResponse) -> bool {
+        let timestamp = price.price.block_timestamp;
+        if timestamp < self.settle_at {
+            return false;
+        }
+        if timestamp <= self.deadline() {
+            self.status = SettlementStatus::Finalized;
+            self.price = Some(price.clone());
+        } else {
+            self.status = SettlementStatus::Missed;
+        }
+        self.finalized_at = Some(env.block.time);
+        true
+    }
+}
+
+impl<'a> Contract<'a> {
+    pub(crate) fn register_settlement(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        pair: CurrencyPair,
+        settle_at: Timestamp,
+        max_delay: Option<u64>,
+    ) -> Result<Response, ContractError> {
+        // an earlier settlement price can no longer be observed
+        if settle_at <= env.block.time {
+            return Err(ContractError::InvalidSettleAt {});
+        }
+        let max_delay = max_delay.unwrap_or(DEFAULT_SETTLEMENT_DELAY);
+        if max_delay == 0 || max_delay > MAX_SETTLEMENT_DELAY {
+            return Err(ContractError::InvalidSettlementDelay { max: MAX_SETTLEMENT_DELAY });
+        }
+        // fails on unknown pairs
+        self.get_price(deps.as_ref(), env, pair.clone())?;
+
+        let id = self.settlement_count.may_load(deps.storage)?.unwrap_or_default() + 1;
+        self.settlement_count.save(deps.storage, &id)?;
+        let settlement = Settlement {
+            id,
+            owner: info.sender,
+            pair: pair.to_string(),
+            settle_at,
+            max_delay,
+            status: SettlementStatus::Pending,
+            price: None,
+            finalized_at: None,
+        };
+        self.settlements.save(deps.storage, id, &settlement)?;
+        self.pending_settlements.save(deps.storage, (&settlement.pair, settle_at.nanos(), id), &())?;
+
+        Ok(Response::new()
+            .add_attribute("action", "register_settlement")
+            .add_attribute("settlement_id", id.to_string())
+            .add_attribute("pair", settlement.pair)
+            .add_attribute("settle_at", settle_at.to_string())
+            .add_attribute("max_delay", max_delay.to_string()))
+    }
+
+    // called for every recorded price, so the settlement price is the first recorded one
+    // at or after `settle_at` no matter who records it, true when due settlements are left
+    // pending beyond the per record bound
+    pub(crate) fn settle_due(&self, storage: &mut dyn Storage, env: &Env, pair_id: &str, price: &GetPriceResponse) -> StdResult<bool> {
+        let due = self.pending_settlements
+            .sub_prefix(pair_id)
+            .keys(storage, None, Some(Bound::inclusive((price.price.block_timestamp.nanos(), u64::MAX))), Order::Ascending)
+            .take(MAX_SETTLEMENTS_PER_RECORD + 1)
+            .collect::<StdResult<Vec<_>>>()?;
+        for (_, id) in due.iter().take(MAX_SETTLEMENTS_PER_RECORD) {
+            let mut settlement = self.settlements.load(storage, *id)?;
+            settlement.settle(env, price);
+            self.save_settled(storage, &settlement)?;
+        }
+        Ok(due.len() > MAX_SETTLEMENTS_PER_RECORD)
+    }
+
+    fn save_settled(&self, storage: &mut dyn Storage, settlement: &Settlement) -> StdResult<()> {
+        self.pending_settlements.remove(storage, (&settlement.pair, settlement.settle_at.nanos(), settlement.id));
+        self.settlements.save(storage, settlement.id, settlement)
+    }
+
+    // permissionless, records the current price of the pair, which settles it when due
+    pub(crate) fn finalize_settlement(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        id: u64,
+    ) -> Result<Response, ContractError> {
+        let settlement = self.settlements.load(deps.storage, id)?;
+        if settlement.status != SettlementStatus::Pending {
+            return Err(ContractError::SettlementFinalized { id });
+        }
+        if env.block.time < settlement.settle_at {
+            return Err(ContractError::SettlementNotDue { id });
+        }
+
+        let pair = settlement.pair.parse()?;
+        let price = self.get_price(deps.as_ref(), env.clone(), pair)?;
+        let recorded = self.record_prices(deps.storage, &env, &info.sender, std::slice::from_ref(&settlement.pair), std::slice::from_ref(&price))?;
+
+        // beyond the per record bound, or not settled by this price
+        let mut settlement = self.settlements.load(deps.storage, id)?;
+        if settlement.status == SettlementStatus::Pending && !settlement.settle(&env, &price) {
+            // slinky only keeps the latest price, once past the deadline no price can settle it
+            if env.block.time <= settlement.deadline() {
+                return Err(ContractError::SettlementNotDue { id });
+            }
+            settlement.status = SettlementStatus::Missed;
+            settlement.finalized_at = Some(env.block.time);
+        }
+        self.save_settled(deps.storage, &settlement)?;
+
+        Ok(Response::new()
+            .add_attribute("action", "finalize_settlement")
+            .add_attribute("settlement_id", id.to_string())
+            .add_attribute("status", settlement.status.to_string())
+            .add_attribute("useful", recorded.keeper.useful.to_string())
+            .add_attributes((!recorded.deferred_settlements.is_empty()).then(|| ("deferred_settlements", recorded.deferred_settlements.join(","))))
+            .add_event(price_event("finalize_settlement", &settlement.pair, &price))
+            .add_messages(recorded.keeper.payout))
+    }
+
+    pub(crate) fn get_settlement(&self, deps: Deps, _env: Env, id: u64) -> Result<Settlement, ContractError> {
+        let settlement = self.settlements.load(deps.storage, id)?;
+        self.assert_pairs_not_paused(deps.storage, std::slice::from_ref(&settlement.pair))?;
+        Ok(settlement)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cosmwasm_std::Int256;
+
+    use super::*;
+    use crate::msgs::{ExecuteMsg, QueryMsg};
+    use crate::testing::{attribute, error_of, Suite, KEEPER, USER};
+
+    fn register(suite: &mut Suite, seconds: u64, max_delay: Option<u64>) -> anyhow::Result<cw_multi_test::AppResponse> {
+        let settle_at = suite.app.block_info().time.plus_seconds(seconds);
+        suite.execute(USER, &ExecuteMsg::RegisterSettlement { pair: "BTC/USD".parse().unwrap(), settle_at, max_delay }, &[])
+    }
+
+    fn settlement(suite: &Suite, id: u64) -> Settlement {
+        suite.query(&QueryMsg::GetSettlement { id }).unwrap()
+    }
+
+    fn record(suite: &mut Suite) {
+        suite.execute(KEEPER, &ExecuteMsg::QuoteAndRecord { pairs: vec!["BTC/USD".parse().unwrap()] }, &[]).unwrap();
+    }
+
+    #[test]
+    fn first_recording_after_settle_at_settles() {
+        let mut suite = Suite::new();
+        suite.set_price("BTC/USD", 100, 0);
+        register(&mut suite, 10, None).unwrap();
+        let before = settlement(&suite, 1);
+        assert_eq!((before.status, before.max_delay), (SettlementStatus::Pending, DEFAULT_SETTLEMENT_DELAY));
+
+        // recordings before settle_at leave it pending
+        suite.advance(5);
+        suite.set_price("BTC/USD", 101, 0);
+        record(&mut suite);
+        assert_eq!(settlement(&suite, 1).status, SettlementStatus::Pending);
+
+        suite.advance(5);
+        suite.set_price("BTC/USD", 102, 0);
+        record(&mut suite);
+        let settled = settlement(&suite, 1);
+        assert_eq!(settled.status, SettlementStatus::Finalized);
+        assert_eq!(settled.price.unwrap().price.price, Int256::from(102));
+        assert_eq!(settled.finalized_at, Some(suite.app.block_info().time));
+
+        // later prices and finalizations cannot change it
+        suite.advance(5);
+        suite.set_price("BTC/USD", 103, 0);
+        record(&mut suite);
+        assert_eq!(settlement(&suite, 1).price.unwrap().price.price, Int256::from(102));
+        let err = suite.execute(KEEPER, &ExecuteMsg::FinalizeSettlement { id: 1 }, &[]).unwrap_err();
+        assert_eq!(error_of(err), "Settlement 1 is already finalized");
+    }
+
+    #[test]
+    fn finalize_settles_on_the_current_price() {
+        let mut suite = Suite::new();
+        suite.set_price("BTC/USD", 100, 0);
+        register(&mut suite, 10, None).unwrap();
+        let err = suite.execute(KEEPER, &ExecuteMsg::FinalizeSettlement { id: 1 }, &[]).unwrap_err();
+        assert_eq!(error_of(err), "No price at or after settle_at for settlement 1 yet");
+
+        // the price is older than settle_at
+        suite.advance(10);
+        let err = suite.execute(KEEPER, &ExecuteMsg::FinalizeSettlement { id: 1 }, &[]).unwrap_err();
+        assert_eq!(error_of(err), "No price at or after settle_at for settlement 1 yet");
+
+        suite.set_price("BTC/USD", 101, 0);
+        let res = suite.execute(KEEPER, &ExecuteMsg::FinalizeSettlement { id: 1 }, &[]).unwrap();
+        assert_eq!(attribute(&res, "status"), "finalized");
+        assert_eq!(settlement(&suite, 1).price.unwrap().price.price, Int256::from(101));
+    }
+
+    #[test]
+    fn missed_beyond_max_delay() {
+        let mut suite = Suite::new();
+        suite.set_price("BTC/USD", 100, 0);
+        register(&mut suite, 10, Some(30)).unwrap();
+        register(&mut suite, 10, Some(30)).unwrap();
+
+        // a recording after the deadline does not settle on its price
+        suite.advance(41);
+        suite.set_price("BTC/USD", 101, 0);
+        record(&mut suite);
+        let missed = settlement(&suite, 1);
+        assert_eq!((missed.status, missed.price), (SettlementStatus::Missed, None));
+
+        // neither does a finalization of a stale price
+        let mut suite = Suite::new();
+        suite.set_price("BTC/USD", 100, 0);
+        register(&mut suite, 10, Some(30)).unwrap();
+        suite.advance(41);
+        let res = suite.execute(KEEPER, &ExecuteMsg::FinalizeSettlement { id: 1 }, &[]).unwrap();
+        assert_eq!(attribute(&res, "status"), "missed");
+        assert_eq!(settlement(&suite, 1).status, SettlementStatus::Missed);
+    }
+
+    #[test]
+    fn register_validation() {
+        let mut suite = Suite::new();
+        suite.set_price("BTC/USD", 100, 0);
+        assert_eq!(error_of(register(&mut suite, 0, None).unwrap_err()), "settle_at must be in the future");
+        let invalid = "Settlement max_delay must be positive and at most 60 seconds";
+        assert_eq!(error_of(register(&mut suite, 10, Some(0)).unwrap_err()), invalid);
+        assert_eq!(error_of(register(&mut suite, 10, Some(MAX_SETTLEMENT_DELAY + 1)).unwrap_err()), invalid);
+        let res = register(&mut suite, 10, Some(MAX_SETTLEMENT_DELAY)).unwrap();
+        assert_eq!(attribute(&res, "max_delay"), "60");
+    }
+
+    #[test]
+    fn settlements_beyond_the_record_bound_are_deferred() {
+        let mut suite = Suite::new();
+        suite.set_price("BTC/USD", 100, 0);
+        for _ in 0..=MAX_SETTLEMENTS_PER_RECORD {
+            register(&mut suite, 10, None).unwrap();
+        }
+
+        suite.advance(10);
+        suite.set_price("BTC/USD", 101, 0);
+        let res = suite.execute(KEEPER, &ExecuteMsg::QuoteAndRecord { pairs: vec!["BTC/USD".parse().unwrap()] }, &[]).unwrap();
+        assert_eq!(attribute(&res, "deferred_settlements"), "BTC/USD");
+        assert_eq!(settlement(&suite, 10).status, SettlementStatus::Finalized);
+        assert_eq!(settlement(&suite, 11).status, SettlementStatus::Pending);
+
+        // the next record settles the rest on the same price
+        let res = suite.execute(KEEPER, &ExecuteMsg::QuoteAndRecord { pairs: vec!["BTC/USD".parse().unwrap()] }, &[]).unwrap();
+        assert_eq!(attribute(&res, "deferred_settlements"), "");
+        assert_eq!(settlement(&suite, 11).price.unwrap().price.price, Int256::from(101));
+    }
+}
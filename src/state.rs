@@ -1,13 +1,141 @@
-pub struct Contract {}
+use cosmwasm_std::{Addr, Coin, Uint128};
+use cw_storage_plus::{Item, Map};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
 
-impl Default for Contract {
+use crate::bounds::PairBounds;
+use crate::candle::Candle;
+use crate::cumulative::CumulativePrice;
+use crate::ibc::ChannelSubscription;
+use crate::keeper::KeeperStats;
+use crate::oracle_source::OracleSourceConfig;
+use crate::order::ConditionalOrder;
+use crate::pause::PauseState;
+use crate::receipt::Receipt;
+use crate::settlement::Settlement;
+use crate::subscription::{PokeCursor, Subscription};
+use crate::twap::Observation;
+
+pub struct Contract<'a> {
+  pub config: Item<'a, Config>,
+  pub denoms: Map<'a, &'a str, DenomMetadata>,
+  pub oracle_sources: Item<'a, Vec<OracleSourceConfig>>,
+  /// circuit breakers keyed by {Base}/{Quote}
+  pub pair_bounds: Map<'a, &'a str, PairBounds>,
+  pub pyth_feeds: Map<'a, &'a str, FeedPair>,
+  pub subscription_count: Item<'a, u64>,
+  pub subscriptions: Map<'a, u64, Subscription>,
+  /// subscriptions per owner
+  pub subscription_counts: Map<'a, &'a Addr, u32>,
+  pub poke_cursor: Item<'a, PokeCursor>,
+  pub keeper_reward: Item<'a, Coin>,
+  pub reward_pool: Map<'a, &'a str, Uint128>,
+  pub keepers: Map<'a, &'a Addr, KeeperStats>,
+  /// latest slinky nonce seen per {Base}/{Quote}
+  pub last_nonces: Map<'a, &'a str, u64>,
+  /// remote price subscriptions keyed by channel id
+  pub channel_subscriptions: Map<'a, &'a str, ChannelSubscription>,
+  pub guardians: Item<'a, Vec<Addr>>,
+  pub pause_state: Item<'a, PauseState>,
+  pub freshness_thresholds: Item<'a, FreshnessThresholds>,
+  pub order_count: Item<'a, u64>,
+  pub orders: Map<'a, u64, ConditionalOrder>,
+  /// order ids keyed by owner
+  pub orders_by_owner: Map<'a, (&'a Addr, u64), ()>,
+  pub settlement_count: Item<'a, u64>,
+  pub settlements: Map<'a, u64, Settlement>,
+  /// pending settlements keyed by {Base}/{Quote}, settle_at in nanoseconds and id
+  pub pending_settlements: Map<'a, (&'a str, u64, u64), ()>,
+  pub receipt_count: Item<'a, u64>,
+  pub receipts: Map<'a, u64, Receipt>,
+  /// keyed by {Base}/{Quote}
+  pub cumulative_prices: Map<'a, &'a str, CumulativePrice>,
+  /// keyed by {Base}/{Quote} and block time in seconds
+  pub observations: Map<'a, (&'a str, u64), Observation>,
+  /// candle lengths in seconds
+  pub candle_intervals: Item<'a, Vec<u64>>,
+  /// keyed by {Base}/{Quote}, interval and open time in seconds
+  pub candles: Map<'a, (&'a str, u64, u64), Candle>,
+}
+
+impl Default for Contract<'static> {
   fn default() -> Self {
     Self::new()
   }
 }
 
-impl<'a> Contract {
+impl<'a> Contract<'a> {
   fn new() -> Self {
-    Self {}
+    Self {
+      config: Item::new("config"),
+      denoms: Map::new("denoms"),
+      oracle_sources: Item::new("oracle_sources"),
+      pair_bounds: Map::new("pair_bounds"),
+      pyth_feeds: Map::new("pyth_feeds"),
+      subscription_count: Item::new("subscription_count"),
+      subscriptions: Map::new("subscriptions"),
+      subscription_counts: Map::new("subscription_counts"),
+      poke_cursor: Item::new("poke_cursor"),
+      keeper_reward: Item::new("keeper_reward"),
+      reward_pool: Map::new("reward_pool"),
+      keepers: Map::new("keepers"),
+      last_nonces: Map::new("last_nonces"),
+      channel_subscriptions: Map::new("channel_subscriptions"),
+      guardians: Item::new("guardians"),
+      pause_state: Item::new("pause_state"),
+      freshness_thresholds: Item::new("freshness_thresholds"),
+      order_count: Item::new("order_count"),
+      orders: Map::new("orders"),
+      orders_by_owner: Map::new("orders_by_owner"),
+      settlement_count: Item::new("settlement_count"),
+      settlements: Map::new("settlements"),
+      pending_settlements: Map::new("pending_settlements"),
+      receipt_count: Item::new("receipt_count"),
+      receipts: Map::new("receipts"),
+      cumulative_prices: Map::new("cumulative_prices"),
+      observations: Map::new("observations"),
+      candle_intervals: Item::new("candle_intervals"),
+      candles: Map::new("candles"),
+    }
   }
 }
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct Config {
+  pub admin: Addr,
+  /// slinky quote ticker every registered denom is priced against, e.g. "USD"
+  pub quote: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct FreshnessThresholds {
+  pub fresh_seconds: u64,
+  pub stale_seconds: u64,
+  pub nonce_window_blocks: u64,
+}
+
+impl Default for FreshnessThresholds {
+  fn default() -> Self {
+    Self {
+      fresh_seconds: 30,
+      stale_seconds: 300,
+      nonce_window_blocks: 10,
+    }
+  }
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct DenomMetadata {
+  /// slinky base ticker of the denom, e.g. "BITCOIN"
+  pub symbol: String,
+  /// number of decimals of the denom's base unit, e.g. 6 for uinit
+  pub decimals: u32,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct FeedPair {
+  pub base: String,
+  pub quote: String,
+  /// half-life in seconds of the ema reported as `ema_price`
+  pub ema_half_life: u64,
+}
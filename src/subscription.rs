@@ -0,0 +1,472 @@
+use cosmwasm_std::{to_json_binary, Addr, Decimal256, Deps, DepsMut, Env, MessageInfo, Order, Response, StdResult, Storage, SubMsg, Timestamp, Int256, WasmMsg};
+use cw_storage_plus::Bound;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::currency_pair::{dedup_pairs, CurrencyPair};
+use crate::error::ContractError;
+use crate::events::{price_events, price_rejected_event};
+use crate::math::{deviation_bps, price_to_decimal};
+use crate::query::GetPriceResponse;
+use crate::state::Contract;
+
+pub const SUBSCRIPTION_CALLBACK_REPLY_ID: u64 = 1;
+
+const DEFAULT_LIMIT: u32 = 10;
+const MAX_LIMIT: u32 = 30;
+
+/// subscriptions and channels served by a single `poke`, each
+const DEFAULT_POKE_LIMIT: u32 = 10;
+const MAX_POKE_LIMIT: u32 = 30;
+
+/// subscribing is permissionless, every subscription costs gas on `poke`
+pub const MAX_SUBSCRIPTIONS_PER_OWNER: u32 = 10;
+pub const MAX_SUBSCRIPTION_PAIRS: usize = 20;
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct Subscription {
+    pub id: u64,
+    pub owner: Addr,
+    pub contract: Addr,
+    /// {Base}/{Quote}
+    pub pairs: Vec<String>,
+    /// minimum seconds between two callbacks
+    pub min_interval: u64,
+    /// minimum price move since the last callback, zero to be called back every interval
+    pub deviation_bps: u64,
+    /// block time of the last callback
+    pub last_update: Option<Timestamp>,
+    /// prices sent with the last callback, in `pairs` order
+    pub last_prices: Vec<Int256>,
+}
+
+/// Message dispatched to subscribed contracts.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum SubscriberExecuteMsg {
+    OraclePriceUpdate {
+        subscription_id: u64,
+        prices: Vec<PairPrice>,
+    },
+}
+
+/// Where the next `poke` continues, subscriptions and channels are served round robin.
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug, Default)]
+pub struct PokeCursor {
+    /// last subscription id served
+    pub subscription: Option<u64>,
+    /// last channel id served
+    pub channel: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct PairPrice {
+    pub pair_id: String,
+    pub price: GetPriceResponse,
+}
+
+impl<'a> Contract<'a> {
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn subscribe(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        pairs: Vec<CurrencyPair>,
+        contract: String,
+        min_interval: u64,
+        deviation_bps: u64,
+    ) -> Result<Response, ContractError> {
+        if pairs.is_empty() {
+            return Err(ContractError::EmptyPairs {});
+        }
+        let pairs: Vec<String> = dedup_pairs(pairs).iter().map(ToString::to_string).collect();
+        if pairs.len() > MAX_SUBSCRIPTION_PAIRS {
+            return Err(ContractError::TooManyPairs { max: MAX_SUBSCRIPTION_PAIRS });
+        }
+        let count = self.subscription_counts.may_load(deps.storage, &info.sender)?.unwrap_or_default();
+        if count >= MAX_SUBSCRIPTIONS_PER_OWNER {
+            return Err(ContractError::TooManySubscriptions { max: MAX_SUBSCRIPTIONS_PER_OWNER });
+        }
+        self.subscription_counts.save(deps.storage, &info.sender, &(count + 1))?;
+        let contract = deps.api.addr_validate(&contract)?;
+        // fails on unknown pairs
+        self.get_prices(deps.as_ref(), env, pairs.clone())?;
+
+        let id = self.subscription_count.may_load(deps.storage)?.unwrap_or_default() + 1;
+        self.subscription_count.save(deps.storage, &id)?;
+        let subscription = Subscription {
+            id,
+            owner: info.sender,
+            contract,
+            pairs,
+            min_interval,
+            deviation_bps,
+            last_update: None,
+            last_prices: vec![],
+        };
+        self.subscriptions.save(deps.storage, id, &subscription)?;
+
+        Ok(Response::new()
+            .add_attribute("action", "subscribe")
+            .add_attribute("subscription_id", id.to_string())
+            .add_attribute("contract", subscription.contract))
+    }
+
+    pub(crate) fn unsubscribe(
+        &self,
+        deps: DepsMut,
+        _env: Env,
+        info: MessageInfo,
+        id: u64,
+    ) -> Result<Response, ContractError> {
+        let subscription = self.subscriptions.load(deps.storage, id)?;
+        let config = self.config.load(deps.storage)?;
+        if subscription.owner != info.sender && config.admin != info.sender {
+            return Err(ContractError::Unauthorized {});
+        }
+        self.subscriptions.remove(deps.storage, id);
+        self.subscription_counts.update(deps.storage, &subscription.owner, |count| -> StdResult<_> {
+            Ok(count.unwrap_or_default().saturating_sub(1))
+        })?;
+
+        Ok(Response::new()
+            .add_attribute("action", "unsubscribe")
+            .add_attribute("subscription_id", id.to_string()))
+    }
+
+    // serves the next `limit` subscriptions and channels after the stored cursor
+    pub(crate) fn poke(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        limit: Option<u32>,
+    ) -> Result<Response, ContractError> {
+        let limit = limit.unwrap_or(DEFAULT_POKE_LIMIT).min(MAX_POKE_LIMIT) as usize;
+        let mut cursor = self.poke_cursor.may_load(deps.storage)?.unwrap_or_default();
+        let subscriptions = self.subscription_page(deps.storage, cursor.subscription, limit)?;
+        let channels = self.channel_subscription_page(deps.storage, cursor.channel.clone(), limit)?;
+        cursor.subscription = subscriptions.last().map(|subscription| subscription.id);
+        cursor.channel = channels.last().map(|(channel_id, _)| channel_id.clone());
+        self.poke_cursor.save(deps.storage, &cursor)?;
+
+        let mut subscribed: Vec<String> = vec![];
+        let subscribed_pairs = subscriptions.iter().flat_map(|subscription| subscription.pairs.iter())
+            .chain(channels.iter().flat_map(|(_, subscription)| subscription.pair_ids.iter()));
+        for pair in subscribed_pairs {
+            if !subscribed.contains(pair) {
+                subscribed.push(pair.clone());
+            }
+        }
+
+        // pairs failing to price are skipped, along with the subscriptions needing them
+        let mut pair_ids = vec![];
+        let mut prices = vec![];
+        let mut skipped = vec![];
+        let mut rejected = vec![];
+        for (pair_id, result) in subscribed.iter().zip(self.get_price_results(deps.as_ref(), env.clone(), &subscribed)?) {
+            match result {
+                Ok(Some(price)) => {
+                    pair_ids.push(pair_id.clone());
+                    prices.push(price);
+                }
+                Ok(None) => skipped.push(pair_id.clone()),
+                Err(err) => {
+                    rejected.extend(price_rejected_event("poke", &err));
+                    skipped.push(pair_id.clone());
+                }
+            }
+        }
+        let recorded = self.record_prices(deps.storage, &env, &info.sender, &pair_ids, &prices)?;
+
+        let mut messages = vec![];
+        for mut subscription in subscriptions {
+            let current = match subscription.pairs
+                .iter()
+                .map(|pair| pair_ids.iter().position(|id| id == pair).map(|i| prices[i].clone()))
+                .collect::<Option<Vec<_>>>()
+            {
+                Some(current) => current,
+                None => continue,
+            };
+
+            if !is_due(&env, &subscription, &current)? {
+                continue;
+            }
+
+            subscription.last_update = Some(env.block.time);
+            subscription.last_prices = current.iter().map(|price| price.price.price).collect();
+            self.subscriptions.save(deps.storage, subscription.id, &subscription)?;
+
+            let msg = SubscriberExecuteMsg::OraclePriceUpdate {
+                subscription_id: subscription.id,
+                prices: subscription.pairs
+                    .iter()
+                    .cloned()
+                    .zip(current)
+                    .map(|(pair_id, price)| PairPrice { pair_id, price })
+                    .collect(),
+            };
+            // a failing subscriber must not block the others
+            messages.push(SubMsg::reply_on_error(
+                WasmMsg::Execute {
+                    contract_addr: subscription.contract.to_string(),
+                    msg: to_json_binary(&msg)?,
+                    funds: vec![],
+                },
+                SUBSCRIPTION_CALLBACK_REPLY_ID,
+            ));
+        }
+
+        let packets = self.push_channel_prices(deps.storage, &env, channels, &pair_ids, &prices)?;
+
+        Ok(Response::new()
+            .add_attribute("action", "poke")
+            .add_attribute("dispatched", messages.len().to_string())
+            .add_attribute("pushed", packets.len().to_string())
+            .add_attributes((!skipped.is_empty()).then(|| ("skipped_pairs", skipped.join(","))))
+            .add_attribute("useful", recorded.keeper.useful.to_string())
+            .add_attributes((!recorded.deferred_settlements.is_empty()).then(|| ("deferred_settlements", recorded.deferred_settlements.join(","))))
+            .add_events(price_events("poke", &pair_ids, &prices))
+            .add_events(rejected)
+            .add_submessages(messages)
+            .add_messages(packets)
+            .add_messages(recorded.keeper.payout))
+    }
+
+    // up to `limit` subscriptions after `after`, wrapping around to the first ones
+    fn subscription_page(&self, storage: &dyn Storage, after: Option<u64>, limit: usize) -> StdResult<Vec<Subscription>> {
+        let mut page = self.subscriptions
+            .range(storage, after.map(Bound::exclusive), None, Order::Ascending)
+            .take(limit)
+            .map(|item| item.map(|(_, subscription)| subscription))
+            .collect::<StdResult<Vec<_>>>()?;
+        if let Some(after) = after.filter(|_| page.len() < limit) {
+            let wrapped = self.subscriptions
+                .range(storage, None, Some(Bound::inclusive(after)), Order::Ascending)
+                .take(limit - page.len())
+                .map(|item| item.map(|(_, subscription)| subscription))
+                .collect::<StdResult<Vec<_>>>()?;
+            page.extend(wrapped);
+        }
+        Ok(page)
+    }
+
+    pub(crate) fn get_subscription(&self, deps: Deps, _env: Env, id: u64) -> StdResult<Subscription> {
+        self.subscriptions.load(deps.storage, id)
+    }
+
+    pub(crate) fn get_subscriptions(&self, deps: Deps, _env: Env, start_after: Option<u64>, limit: Option<u32>) -> StdResult<SubscriptionsResponse> {
+        let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+        let subscriptions = self.subscriptions
+            .range(deps.storage, start_after.map(Bound::exclusive), None, Order::Ascending)
+            .take(limit)
+            .map(|item| item.map(|(_, subscription)| subscription))
+            .collect::<StdResult<Vec<_>>>()?;
+
+        Ok(SubscriptionsResponse { subscriptions })
+    }
+}
+
+fn is_due(env: &Env, subscription: &Subscription, current: &[GetPriceResponse]) -> StdResult<bool> {
+    let last_update = match subscription.last_update {
+        Some(last_update) => last_update,
+        None => return Ok(true),
+    };
+    if env.block.time.seconds() < last_update.seconds().saturating_add(subscription.min_interval) {
+        return Ok(false);
+    }
+    if subscription.deviation_bps == 0 {
+        return Ok(true);
+    }
+
+    let threshold = Decimal256::from_ratio(subscription.deviation_bps, 1u64);
+    for (price, last_price) in current.iter().zip(subscription.last_prices.iter()) {
+        let last_price = price_to_decimal(*last_price, price.decimals)?;
+        if last_price.is_zero() {
+            return Ok(true);
+        }
+        if deviation_bps(price_to_decimal(price.price.price, price.decimals)?, last_price)? >= threshold {
+            return Ok(true);
+        }
+    }
+
+    Ok(false)
+}
+
+#[derive(Clone, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct SubscriptionsResponse {
+    pub subscriptions: Vec<Subscription>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::msgs::{ExecuteMsg, QueryMsg};
+    use crate::testing::{attribute, error_of, mock_subscriber, received_updates, Suite, ADMIN, KEEPER, USER};
+
+    fn subscribe(suite: &mut Suite, owner: &str, pairs: &[&str], contract: &Addr, min_interval: u64, deviation_bps: u64) -> anyhow::Result<u64> {
+        let msg = ExecuteMsg::Subscribe {
+            pairs: pairs.iter().map(|pair| pair.parse().unwrap()).collect(),
+            contract: contract.to_string(),
+            min_interval,
+            deviation_bps,
+        };
+        let res = suite.execute(owner, &msg, &[])?;
+        let id = res.events.iter().flat_map(|event| event.attributes.iter()).find(|attr| attr.key == "subscription_id").unwrap();
+        Ok(id.value.parse().unwrap())
+    }
+
+    fn poke(suite: &mut Suite, limit: Option<u32>) -> cw_multi_test::AppResponse {
+        suite.execute(KEEPER, &ExecuteMsg::Poke { limit }, &[]).unwrap()
+    }
+
+    #[test]
+    fn subscribe_normalizes_pairs() {
+        let mut suite = Suite::new();
+        let subscriber = mock_subscriber(&mut suite, false);
+        suite.set_price("BTC/USD", 100, 0);
+
+        let id = subscribe(&mut suite, USER, &["btc/usd", "BTC/USD"], &subscriber, 60, 0).unwrap();
+        let subscription: Subscription = suite.query(&QueryMsg::GetSubscription { id }).unwrap();
+        assert_eq!(subscription.pairs, vec!["BTC/USD".to_string()]);
+        assert_eq!(subscription.owner, Addr::unchecked(USER));
+
+        assert_eq!(error_of(subscribe(&mut suite, USER, &[], &subscriber, 60, 0).unwrap_err()), ContractError::EmptyPairs {}.to_string());
+        // unknown pairs
+        assert!(subscribe(&mut suite, USER, &["ETH/USD"], &subscriber, 60, 0).is_err());
+    }
+
+    #[test]
+    fn subscriptions_are_capped() {
+        let mut suite = Suite::new();
+        let subscriber = mock_subscriber(&mut suite, false);
+        let pairs: Vec<String> = (0..=MAX_SUBSCRIPTION_PAIRS).map(|i| format!("T{}/USD", i)).collect();
+        for pair in pairs.iter() {
+            suite.set_price(pair, 1, 0);
+        }
+        let pairs: Vec<&str> = pairs.iter().map(String::as_str).collect();
+        let err = subscribe(&mut suite, USER, &pairs, &subscriber, 60, 0).unwrap_err();
+        assert_eq!(error_of(err), ContractError::TooManyPairs { max: MAX_SUBSCRIPTION_PAIRS }.to_string());
+
+        let mut ids = vec![];
+        for _ in 0..MAX_SUBSCRIPTIONS_PER_OWNER {
+            ids.push(subscribe(&mut suite, USER, &["T0/USD"], &subscriber, 60, 0).unwrap());
+        }
+        let err = subscribe(&mut suite, USER, &["T0/USD"], &subscriber, 60, 0).unwrap_err();
+        assert_eq!(error_of(err), ContractError::TooManySubscriptions { max: MAX_SUBSCRIPTIONS_PER_OWNER }.to_string());
+        // other owners have their own cap
+        subscribe(&mut suite, ADMIN, &["T0/USD"], &subscriber, 60, 0).unwrap();
+
+        // only the owner or the admin unsubscribes, freeing a slot
+        assert!(suite.execute(KEEPER, &ExecuteMsg::Unsubscribe { id: ids[0] }, &[]).is_err());
+        suite.execute(ADMIN, &ExecuteMsg::Unsubscribe { id: ids[0] }, &[]).unwrap();
+        subscribe(&mut suite, USER, &["T0/USD"], &subscriber, 60, 0).unwrap();
+    }
+
+    #[test]
+    fn poke_dispatches_due_callbacks() {
+        let mut suite = Suite::new();
+        let subscriber = mock_subscriber(&mut suite, false);
+        suite.set_price("BTC/USD", 10_000, 2);
+        let id = subscribe(&mut suite, USER, &["BTC/USD"], &subscriber, 60, 100).unwrap();
+
+        // the first callback is sent right away
+        let res = poke(&mut suite, None);
+        assert_eq!(attribute(&res, "dispatched"), "1");
+        let updates = received_updates(&suite, &subscriber);
+        let SubscriberExecuteMsg::OraclePriceUpdate { subscription_id, prices } = &updates[0];
+        assert_eq!(*subscription_id, id);
+        assert_eq!(prices[0].pair_id, "BTC/USD");
+        assert_eq!(prices[0].price.price.price, Int256::from(10_000u64));
+
+        // moved 1% but within min_interval
+        suite.advance(30);
+        suite.set_price("BTC/USD", 10_100, 2);
+        assert_eq!(attribute(&poke(&mut suite, None), "dispatched"), "0");
+
+        // moved less than deviation_bps since the last callback
+        suite.advance(30);
+        suite.set_price("BTC/USD", 10_099, 2);
+        assert_eq!(attribute(&poke(&mut suite, None), "dispatched"), "0");
+
+        suite.set_price("BTC/USD", 10_100, 2);
+        assert_eq!(attribute(&poke(&mut suite, None), "dispatched"), "1");
+        assert_eq!(received_updates(&suite, &subscriber).len(), 2);
+    }
+
+    #[test]
+    fn failing_subscriber_does_not_revert_poke() {
+        let mut suite = Suite::new();
+        let failing = mock_subscriber(&mut suite, true);
+        let subscriber = mock_subscriber(&mut suite, false);
+        suite.set_price("BTC/USD", 100, 0);
+        subscribe(&mut suite, USER, &["BTC/USD"], &failing, 0, 0).unwrap();
+        subscribe(&mut suite, USER, &["BTC/USD"], &subscriber, 0, 0).unwrap();
+
+        let res = poke(&mut suite, None);
+        assert_eq!(attribute(&res, "dispatched"), "2");
+        assert_eq!(received_updates(&suite, &subscriber).len(), 1);
+    }
+
+    #[test]
+    fn poke_skips_failing_pairs() {
+        let mut suite = Suite::new();
+        let subscriber = mock_subscriber(&mut suite, false);
+        suite.set_price("BTC/USD", 100, 0);
+        suite.set_price("ETH/USD", 10, 0);
+        let healthy = subscribe(&mut suite, USER, &["BTC/USD"], &subscriber, 0, 0).unwrap();
+        subscribe(&mut suite, USER, &["BTC/USD", "ETH/USD"], &subscriber, 0, 0).unwrap();
+
+        // delisted after subscribing
+        suite.slinky.remove_pair("ETH/USD");
+        let res = poke(&mut suite, None);
+        assert_eq!(attribute(&res, "dispatched"), "1");
+        assert_eq!(attribute(&res, "skipped_pairs"), "ETH/USD");
+        let SubscriberExecuteMsg::OraclePriceUpdate { subscription_id, .. } = &received_updates(&suite, &subscriber)[0];
+        assert_eq!(*subscription_id, healthy);
+
+        // paused pairs are skipped the same way
+        suite.set_price("ETH/USD", 10, 0);
+        let target = crate::msgs::PauseTarget::Pair { pair_id: "ETH/USD".parse().unwrap() };
+        suite.execute(ADMIN, &ExecuteMsg::Pause { target }, &[]).unwrap();
+        let res = poke(&mut suite, None);
+        assert_eq!(attribute(&res, "skipped_pairs"), "ETH/USD");
+    }
+
+    #[test]
+    fn poke_serves_subscriptions_round_robin() {
+        let mut suite = Suite::new();
+        let subscriber = mock_subscriber(&mut suite, false);
+        suite.set_price("BTC/USD", 100, 0);
+        for _ in 0..3 {
+            subscribe(&mut suite, USER, &["BTC/USD"], &subscriber, 0, 0).unwrap();
+        }
+
+        let served = |suite: &Suite| -> Vec<u64> {
+            received_updates(suite, &subscriber)
+                .into_iter()
+                .map(|SubscriberExecuteMsg::OraclePriceUpdate { subscription_id, .. }| subscription_id)
+                .collect()
+        };
+        poke(&mut suite, Some(2));
+        assert_eq!(served(&suite), vec![1, 2]);
+        poke(&mut suite, Some(2));
+        assert_eq!(served(&suite), vec![1, 2, 3, 1]);
+        poke(&mut suite, Some(2));
+        assert_eq!(served(&suite), vec![1, 2, 3, 1, 2, 3]);
+    }
+
+    #[test]
+    fn is_due_saturates() {
+        let mut suite = Suite::new();
+        let subscriber = mock_subscriber(&mut suite, false);
+        suite.set_price("BTC/USD", 100, 0);
+        subscribe(&mut suite, USER, &["BTC/USD"], &subscriber, u64::MAX, 0).unwrap();
+
+        assert_eq!(attribute(&poke(&mut suite, None), "dispatched"), "1");
+        suite.advance(1_000_000);
+        assert_eq!(attribute(&poke(&mut suite, None), "dispatched"), "0");
+    }
+}
@@ -0,0 +1,309 @@
+// shared test helpers: an in-memory slinky oracle answering the stargate queries
+// and a multi-test app running the contract against it
+
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::rc::Rc;
+
+use cosmwasm_std::testing::{MockApi, MockQuerier, MockStorage};
+use cosmwasm_std::{
+    from_json, to_json_binary, Addr, Api, Binary, BlockInfo, Coin, ContractResult, Deps, DepsMut, Empty, Env, MessageInfo,
+    OwnedDeps, Querier, QuerierResult, QueryRequest, Response, StdError, StdResult, Storage, SystemError, SystemResult,
+    Timestamp,
+};
+use cw_multi_test::{
+    App, AppBuilder, AppResponse, BankKeeper, ContractWrapper, DistributionKeeper, Executor, FailingModule, GovFailingModule,
+    IbcAcceptingModule, StakeKeeper, Stargate, WasmKeeper,
+};
+use cw_storage_plus::Item;
+use protobuf::Message;
+use serde::de::DeserializeOwned;
+
+use crate::msgs::{ExecuteMsg, InstantiateMsg, QueryMsg, SudoMsg};
+use crate::query::GetPriceResponse;
+use crate::slinky_oracle::{GetPriceRequest, GetPricesRequest};
+use crate::subscription::SubscriberExecuteMsg;
+use crate::timestamp::convert_timestamp_to_iso_string;
+
+pub const ADMIN: &str = "admin";
+pub const USER: &str = "user";
+pub const KEEPER: &str = "keeper";
+pub const DENOM: &str = "uinit";
+
+#[derive(Clone, Debug)]
+struct MockPrice {
+    price: Option<(String, Timestamp, u64)>,
+    nonce: u64,
+    decimals: u64,
+    id: u64,
+}
+
+/// Currency pairs and prices served by the mocked `slinky.oracle.v1.Query` service.
+#[derive(Clone, Default)]
+pub struct MockSlinky {
+    pairs: Rc<RefCell<BTreeMap<String, MockPrice>>>,
+}
+
+impl MockSlinky {
+    /// lists the pair without a price, like a freshly added market
+    pub fn add_pair(&self, pair_id: &str, decimals: u64) {
+        let mut pairs = self.pairs.borrow_mut();
+        let id = pairs.len() as u64;
+        pairs.entry(pair_id.to_string()).or_insert(MockPrice { price: None, nonce: 0, decimals, id });
+    }
+
+    /// reports a new price, advancing the pair's nonce
+    pub fn set_price(&self, pair_id: &str, price: i128, decimals: u64, block: &BlockInfo) {
+        self.add_pair(pair_id, decimals);
+        let mut pairs = self.pairs.borrow_mut();
+        let entry = pairs.get_mut(pair_id).unwrap();
+        entry.price = Some((price.to_string(), block.time, block.height));
+        entry.nonce += 1;
+        entry.decimals = decimals;
+    }
+
+    /// delists the pair, queries for it fail from now on
+    pub fn remove_pair(&self, pair_id: &str) {
+        self.pairs.borrow_mut().remove(pair_id);
+    }
+
+    fn price_json(&self, pair_id: &str) -> Result<String, String> {
+        let pairs = self.pairs.borrow();
+        let entry = pairs.get(pair_id).ok_or_else(|| format!("no price for currency pair {}", pair_id))?;
+        Ok(price_json(entry))
+    }
+
+    fn handle(&self, path: &str, data: &[u8]) -> Result<Binary, String> {
+        match path {
+            "/slinky.oracle.v1.Query/GetPrice" => {
+                let request = GetPriceRequest::parse_from_bytes(data).map_err(|err| err.to_string())?;
+                let pair = request.currency_pair.into_option().ok_or("missing currency pair")?;
+                let price = self.price_json(&format!("{}/{}", pair.Base, pair.Quote))?;
+                Ok(Binary::from(price.into_bytes()))
+            }
+            "/slinky.oracle.v1.Query/GetPrices" => {
+                let request = GetPricesRequest::parse_from_bytes(data).map_err(|err| err.to_string())?;
+                let prices = request
+                    .currency_pair_ids
+                    .iter()
+                    .map(|pair_id| self.price_json(pair_id))
+                    .collect::<Result<Vec<_>, _>>()?;
+                Ok(Binary::from(format!("{{\"prices\":[{}]}}", prices.join(",")).into_bytes()))
+            }
+            "/slinky.oracle.v1.Query/GetAllCurrencyPairs" => {
+                let pairs = self
+                    .pairs
+                    .borrow()
+                    .keys()
+                    .map(|pair_id| {
+                        let (base, quote) = pair_id.split_once('/').unwrap();
+                        format!("{{\"Base\":\"{}\",\"Quote\":\"{}\"}}", base, quote)
+                    })
+                    .collect::<Vec<_>>();
+                Ok(Binary::from(format!("{{\"currency_pairs\":[{}]}}", pairs.join(",")).into_bytes()))
+            }
+            _ => Err(format!("unexpected stargate query {}", path)),
+        }
+    }
+}
+
+// as the chain encodes it, uint64s as strings and timestamps in RFC 3339
+fn price_json(entry: &MockPrice) -> String {
+    let price = match &entry.price {
+        Some((price, time, height)) => format!(
+            "\"price\":{{\"price\":\"{}\",\"block_timestamp\":\"{}\",\"block_height\":\"{}\"}},",
+            price,
+            convert_timestamp_to_iso_string(time),
+            height,
+        ),
+        None => String::new(),
+    };
+    format!("{{{}\"nonce\":\"{}\",\"decimals\":\"{}\",\"id\":\"{}\"}}", price, entry.nonce, entry.decimals, entry.id)
+}
+
+impl Stargate for MockSlinky {
+    fn query(
+        &self,
+        _api: &dyn Api,
+        _storage: &dyn Storage,
+        _querier: &dyn Querier,
+        _block: &BlockInfo,
+        path: String,
+        data: Binary,
+    ) -> anyhow::Result<Binary> {
+        self.handle(&path, &data).map_err(anyhow::Error::msg)
+    }
+}
+
+/// `MockQuerier` answering slinky stargate queries, for calling entry points directly.
+pub struct OracleQuerier {
+    base: MockQuerier,
+    slinky: MockSlinky,
+}
+
+impl Querier for OracleQuerier {
+    fn raw_query(&self, bin_request: &[u8]) -> QuerierResult {
+        match from_json::<QueryRequest<Empty>>(bin_request) {
+            Ok(QueryRequest::Stargate { path, data }) => match self.slinky.handle(&path, &data) {
+                Ok(res) => SystemResult::Ok(ContractResult::Ok(res)),
+                Err(err) => SystemResult::Ok(ContractResult::Err(err)),
+            },
+            Ok(_) => self.base.raw_query(bin_request),
+            Err(err) => SystemResult::Err(SystemError::InvalidRequest {
+                error: err.to_string(),
+                request: Binary::from(bin_request),
+            }),
+        }
+    }
+}
+
+pub fn mock_deps(slinky: &MockSlinky) -> OwnedDeps<MockStorage, MockApi, OracleQuerier> {
+    OwnedDeps {
+        storage: MockStorage::default(),
+        api: MockApi::default(),
+        querier: OracleQuerier { base: MockQuerier::default(), slinky: slinky.clone() },
+        custom_query_type: Default::default(),
+    }
+}
+
+pub type OracleApp = App<
+    BankKeeper,
+    MockApi,
+    MockStorage,
+    FailingModule<Empty, Empty, Empty>,
+    WasmKeeper<Empty, Empty>,
+    StakeKeeper,
+    DistributionKeeper,
+    IbcAcceptingModule,
+    GovFailingModule,
+    MockSlinky,
+>;
+
+/// The contract instantiated by `ADMIN` in a multi-test app backed by `MockSlinky`.
+pub struct Suite {
+    pub app: OracleApp,
+    pub slinky: MockSlinky,
+    pub contract: Addr,
+}
+
+impl Suite {
+    pub fn new() -> Self {
+        let slinky = MockSlinky::default();
+        let mut app = AppBuilder::new()
+            .with_ibc(IbcAcceptingModule::new())
+            .with_stargate(slinky.clone())
+            .build(|router, _, storage| {
+                for account in [ADMIN, USER, KEEPER] {
+                    router
+                        .bank
+                        .init_balance(storage, &Addr::unchecked(account), vec![Coin::new(1_000_000_000, DENOM)])
+                        .unwrap();
+                }
+            });
+
+        let code = ContractWrapper::new(crate::entry::execute, crate::entry::instantiate, crate::entry::query)
+            .with_sudo(crate::entry::sudo)
+            .with_reply(crate::entry::reply)
+            .with_migrate(crate::entry::migrate);
+        let code_id = app.store_code(Box::new(code));
+        let contract = app
+            .instantiate_contract(
+                code_id,
+                Addr::unchecked(ADMIN),
+                &InstantiateMsg { admin: None, quote: None },
+                &[],
+                "slinky",
+                Some(ADMIN.to_string()),
+            )
+            .unwrap();
+
+        Suite { app, slinky, contract }
+    }
+
+    /// reports `price` for the pair at the current block
+    pub fn set_price(&self, pair_id: &str, price: i128, decimals: u64) {
+        self.slinky.set_price(pair_id, price, decimals, &self.app.block_info());
+    }
+
+    pub fn advance(&mut self, seconds: u64) {
+        self.app.update_block(|block| {
+            block.height += 1;
+            block.time = block.time.plus_seconds(seconds);
+        });
+    }
+
+    pub fn execute(&mut self, sender: &str, msg: &ExecuteMsg, funds: &[Coin]) -> anyhow::Result<AppResponse> {
+        self.app.execute_contract(Addr::unchecked(sender), self.contract.clone(), msg, funds)
+    }
+
+    pub fn sudo(&mut self, msg: &SudoMsg) -> anyhow::Result<AppResponse> {
+        self.app.wasm_sudo(self.contract.clone(), msg)
+    }
+
+    pub fn balance(&self, address: &str) -> u128 {
+        self.app.wrap().query_balance(address, DENOM).unwrap().amount.u128()
+    }
+
+    pub fn query<T: DeserializeOwned>(&self, msg: &QueryMsg) -> cosmwasm_std::StdResult<T> {
+        self.app.wrap().query_wasm_smart(self.contract.clone(), msg)
+    }
+}
+
+/// A price feed contract answering every query with the price it was instantiated with.
+pub fn mock_feed(suite: &mut Suite, price: &GetPriceResponse) -> Addr {
+    const PRICE: Item<GetPriceResponse> = Item::new("price");
+    let code = ContractWrapper::new(
+        |_: DepsMut, _: Env, _: MessageInfo, _: Empty| -> StdResult<Response> { Ok(Response::new()) },
+        |deps: DepsMut, _: Env, _: MessageInfo, price: GetPriceResponse| -> StdResult<Response> {
+            PRICE.save(deps.storage, &price)?;
+            Ok(Response::new())
+        },
+        |deps: Deps, _: Env, _: QueryMsg| -> StdResult<Binary> { to_json_binary(&PRICE.load(deps.storage)?) },
+    );
+    let code_id = suite.app.store_code(Box::new(code));
+    suite.app.instantiate_contract(code_id, Addr::unchecked(ADMIN), price, &[], "feed", None).unwrap()
+}
+
+/// A subscriber contract storing every price update, or failing on them when instantiated with `true`.
+pub fn mock_subscriber(suite: &mut Suite, fail: bool) -> Addr {
+    const FAIL: Item<bool> = Item::new("fail");
+    const UPDATES: Item<Vec<SubscriberExecuteMsg>> = Item::new("updates");
+    let code = ContractWrapper::new(
+        |deps: DepsMut, _: Env, _: MessageInfo, msg: SubscriberExecuteMsg| -> StdResult<Response> {
+            if FAIL.load(deps.storage)? {
+                return Err(StdError::generic_err("subscriber failed"));
+            }
+            let mut updates = UPDATES.may_load(deps.storage)?.unwrap_or_default();
+            updates.push(msg);
+            UPDATES.save(deps.storage, &updates)
+                .map(|_| Response::new())
+        },
+        |deps: DepsMut, _: Env, _: MessageInfo, fail: bool| -> StdResult<Response> {
+            FAIL.save(deps.storage, &fail)?;
+            Ok(Response::new())
+        },
+        |deps: Deps, _: Env, _: Empty| -> StdResult<Binary> { to_json_binary(&UPDATES.may_load(deps.storage)?.unwrap_or_default()) },
+    );
+    let code_id = suite.app.store_code(Box::new(code));
+    suite.app.instantiate_contract(code_id, Addr::unchecked(ADMIN), &fail, &[], "subscriber", None).unwrap()
+}
+
+/// price updates received by a `mock_subscriber`
+pub fn received_updates(suite: &Suite, subscriber: &Addr) -> Vec<SubscriberExecuteMsg> {
+    suite.app.wrap().query_wasm_smart(subscriber, &Empty {}).unwrap()
+}
+
+/// value of the first attribute named `key` in any event, empty when missing
+pub fn attribute(res: &AppResponse, key: &str) -> String {
+    res.events
+        .iter()
+        .flat_map(|event| event.attributes.iter())
+        .find(|attr| attr.key == key)
+        .map(|attr| attr.value.clone())
+        .unwrap_or_default()
+}
+
+/// the root error message of a failed execute
+pub fn error_of(err: anyhow::Error) -> String {
+    err.root_cause().to_string()
+}
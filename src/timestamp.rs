@@ -1,4 +1,7 @@
-use cosmwasm_std::Timestamp;
+// lints allowed rather than reformatting the original code
+#![allow(clippy::needless_return, clippy::assign_op_pattern, clippy::manual_is_multiple_of, clippy::needless_late_init)]
+
+use cosmwasm_std::{StdError, StdResult, Timestamp};
 
 const SECONDS_IN_MINUTE: u64 = 60;
 const SECONDS_IN_HOUR: u64 = 3600;
@@ -9,64 +12,109 @@ const SECONDS_BETWEEN_JAN_1_1972_AND_DEC_31_1999: u64 = 883612800;
 const SECONDS_IN_100_YEARS: u64 = 3155673600;
 const SECONDS_IN_400_YEARS: u64 = 12622780800;
 
-pub fn convert_iso_string_to_timestamp(iso_string: &String) -> Timestamp {
-    let (year, month, day, hour, minute, second, decimal) = convert_date_time_string_to_ymdhms(iso_string);
-    convert_ymdhms_to_timestamp(year, month, day, hour, minute, second, decimal)
+pub fn convert_iso_string_to_timestamp(iso_string: &str) -> StdResult<Timestamp> {
+    let invalid = || StdError::parse_err("Timestamp", format!("invalid RFC 3339 timestamp {}", iso_string));
+    let (year, month, day, hour, minute, second, decimal) = convert_date_time_string_to_ymdhms(iso_string).ok_or_else(invalid)?;
+    convert_ymdhms_to_timestamp(year, month, day, hour, minute, second, decimal).ok_or_else(invalid)
+}
+
+// => YYYY-MM-DDTHH:MM:SS.nnnnnnnnnZ
+pub fn convert_timestamp_to_iso_string(timestamp: &Timestamp) -> String {
+    let seconds = timestamp.seconds();
+    let (year, month, day) = convert_days_to_ymd(seconds / SECONDS_IN_DAY);
+    let second_of_day = seconds % SECONDS_IN_DAY;
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:09}Z",
+        year,
+        month,
+        day,
+        second_of_day / SECONDS_IN_HOUR,
+        second_of_day % SECONDS_IN_HOUR / SECONDS_IN_MINUTE,
+        second_of_day % SECONDS_IN_MINUTE,
+        timestamp.subsec_nanos(),
+    )
+}
+
+// days since 1970-01-01 => (year, month, day)
+fn convert_days_to_ymd(days: u64) -> (u64, u64, u64) {
+    let mut year = 1970;
+    let mut days = days;
+    loop {
+        let days_in_year = if is_leap_year(year) { 366 } else { 365 };
+        if days < days_in_year {
+            break;
+        }
+        days -= days_in_year;
+        year += 1;
+    }
+
+    let mut month = 1;
+    while month < 12 && days * SECONDS_IN_DAY >= add_month_seconds(year, month + 1) {
+        month += 1;
+    }
+    let day = days - add_month_seconds(year, month) / SECONDS_IN_DAY + 1;
+
+    (year, month, day)
 }
 
 // YYYY-MM-DDTHH:MM:SS.ss~Z => (year, month, day, hour, minute, second, decimal)
-fn convert_date_time_string_to_ymdhms(iso_string: &String) -> (u64, u64, u64, u64, u64, u64, u64) {
+fn convert_date_time_string_to_ymdhms(iso_string: &str) -> Option<(u64, u64, u64, u64, u64, u64, u64)> {
     let mut split = iso_string.split("T");
-    let iso_date = split.next().unwrap();
-    let iso_time = split.next().unwrap();
+    let iso_date = split.next()?;
+    let iso_time = split.next()?;
 
-    let (year, month, day) = convert_date_string_to_ymd(iso_date);
-    let (hour, minute, second, decimal) = split_time_int(iso_time);
+    let (year, month, day) = convert_date_string_to_ymd(iso_date)?;
+    let (hour, minute, second, decimal) = split_time_int(iso_time)?;
 
-    (year, month, day, hour, minute, second, decimal)
+    let valid = year >= 1970 && (1..=12).contains(&month) && (1..=days_in_month(year, month)).contains(&day)
+        && hour < 24 && minute < 60 && second < 60;
+    valid.then_some((year, month, day, hour, minute, second, decimal))
 }
 
-fn convert_date_string_to_ymd(iso_date: &str) -> (u64, u64, u64) {
+fn convert_date_string_to_ymd(iso_date: &str) -> Option<(u64, u64, u64)> {
     let mut split = iso_date.split("-");
-    let year = split.next().unwrap();
-    let month = split.next().unwrap();
-    let day = split.next().unwrap();
+    let year = split.next()?;
+    let month = split.next()?;
+    let day = split.next()?;
 
-    (year.parse().unwrap(), month.parse().unwrap(), day.parse().unwrap())
+    Some((year.parse().ok()?, month.parse().ok()?, day.parse().ok()?))
 }
 
-fn split_time_int(iso_time: &str) -> (u64, u64, u64, u64) {
+fn split_time_int(iso_time: &str) -> Option<(u64, u64, u64, u64)> {
     let mut split = iso_time.split(":");
-    let hour = split.next().unwrap();
-    let minute = split.next().unwrap();
-    let remain = split.next().unwrap();
+    let hour = split.next()?;
+    let minute = split.next()?;
+    let remain = split.next()?;
 
     let mut second_split = remain.split(".");
-    let second = second_split.next().unwrap();
+    let second = second_split.next()?.trim_end_matches('Z');
     let second_remain = second_split.next().unwrap_or("0");
     // to remove Z
     let mut decimal_split = second_remain.split("Z"); 
-    let decimal = decimal_split.next().unwrap();
-    // pad zeros
+    let decimal = decimal_split.next()?;
+    // pad zeros, nanosecond precision at most
     let decimal = &format!("{:0<9}", decimal);
+    let decimal = decimal.get(..9)?;
 
 
-    (hour.parse().unwrap(), minute.parse().unwrap(), second.parse().unwrap(), decimal.parse().unwrap())    
+    Some((hour.parse().ok()?, minute.parse().ok()?, second.parse().ok()?, decimal.parse().ok()?))
 }
 
-fn convert_ymdhms_to_timestamp(year: u64, month: u64, day: u64, hour: u64, minute: u64, second: u64, decimal: u64) -> Timestamp {
+// none when the timestamp does not fit in u64 nanoseconds
+fn convert_ymdhms_to_timestamp(year: u64, month: u64, day: u64, hour: u64, minute: u64, second: u64, decimal: u64) -> Option<Timestamp> {
     let mut ts: u64 = 0;
-    ts = add_year_to_second(ts, year);
-    ts += add_month_seconds(year, month);
-    ts += (day - 1) * SECONDS_IN_DAY;
-    ts += hour * SECONDS_IN_HOUR;
-    ts += minute * SECONDS_IN_MINUTE;
-    ts += second;
-
-    return Timestamp::from_nanos(ts * 1_000_000_000 + decimal);
+    ts = add_year_to_second(ts, year)?;
+    ts = ts.checked_add(add_month_seconds(year, month))?;
+    ts = ts.checked_add((day - 1) * SECONDS_IN_DAY)?;
+    ts = ts.checked_add(hour * SECONDS_IN_HOUR)?;
+    ts = ts.checked_add(minute * SECONDS_IN_MINUTE)?;
+    ts = ts.checked_add(second)?;
+
+    return Some(Timestamp::from_nanos(ts.checked_mul(1_000_000_000)?.checked_add(decimal)?));
 }
 
-fn add_year_to_second(ts: u64, year: u64) -> u64 {
+fn add_year_to_second(ts: u64, year: u64) -> Option<u64> {
     let mut year_counter;
     let mut ts = ts;
 
@@ -84,43 +132,43 @@ fn add_year_to_second(ts: u64, year: u64) -> u64 {
             ts += SECONDS_BETWEEN_JAN_1_1972_AND_DEC_31_1999;
             year_counter = 2000;
 
-            (year_counter, ts) = increment_year_and_timestamp(year, year_counter, ts, 400, SECONDS_IN_400_YEARS);
-            (year_counter, ts) = increment_leap_year(year, year_counter, ts);
-            (year_counter, ts) = increment_year_and_timestamp(year, year_counter, ts, 100, SECONDS_IN_100_YEARS);
+            (year_counter, ts) = increment_year_and_timestamp(year, year_counter, ts, 400, SECONDS_IN_400_YEARS)?;
+            (year_counter, ts) = increment_leap_year(year, year_counter, ts)?;
+            (year_counter, ts) = increment_year_and_timestamp(year, year_counter, ts, 100, SECONDS_IN_100_YEARS)?;
         }
 
-        (year_counter, ts) = increment_year_and_timestamp(year, year_counter, ts, 4, SECONDS_IN_FOUR_YEARS_WITH_LEAP_YEAR);
-        (year_counter, ts) = increment_leap_year(year, year_counter, ts);
-        (_, ts) = increment_year_and_timestamp(year, year_counter, ts, 1, SECONDS_IN_YEAR);
+        (year_counter, ts) = increment_year_and_timestamp(year, year_counter, ts, 4, SECONDS_IN_FOUR_YEARS_WITH_LEAP_YEAR)?;
+        (year_counter, ts) = increment_leap_year(year, year_counter, ts)?;
+        (_, ts) = increment_year_and_timestamp(year, year_counter, ts, 1, SECONDS_IN_YEAR)?;
     }
 
-    return ts;
+    return Some(ts);
 }
 
 
-fn increment_year_and_timestamp(year: u64, year_counter: u64, ts: u64, divisor: u64, seconds: u64) -> (u64, u64) {
+fn increment_year_and_timestamp(year: u64, year_counter: u64, ts: u64, divisor: u64, seconds: u64) -> Option<(u64, u64)> {
 
     let mut ts = ts;
     let mut year = year;
 
     let res = (year - year_counter) / divisor;
     year = year_counter + (res * divisor);
-    ts = ts + (res * seconds);
+    ts = ts.checked_add(res.checked_mul(seconds)?)?;
 
-    (year, ts)
+    Some((year, ts))
 }
 
-fn increment_leap_year(year: u64, year_counter: u64, ts: u64) -> (u64, u64) {
+fn increment_leap_year(year: u64, year_counter: u64, ts: u64) -> Option<(u64, u64)> {
     let mut year_counter = year_counter;
     let mut ts = ts;
 
     if (year_counter < year) && is_leap_year(year_counter) {
 
         year_counter += 1;
-        ts += SECONDS_IN_YEAR + SECONDS_IN_DAY;
+        ts = ts.checked_add(SECONDS_IN_YEAR + SECONDS_IN_DAY)?;
     }
 
-    (year_counter, ts)
+    Some((year_counter, ts))
 }
 
 fn is_leap_year(year: u64) -> bool {
@@ -131,6 +179,10 @@ fn is_leap_year(year: u64) -> bool {
     return false;
 }
 
+fn days_in_month(year: u64, month: u64) -> u64 {
+    (add_month_seconds(year, month + 1) - add_month_seconds(year, month)) / SECONDS_IN_DAY
+}
+
 fn add_month_seconds(year: u64, month: u64) -> u64 {
 
     let month_seconds_map: Vec<u64>;
@@ -146,4 +198,53 @@ fn add_month_seconds(year: u64, month: u64) -> u64 {
     }
 
     return month_seconds_map[(month as usize) - 1];
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(iso_string: &str) -> StdResult<Timestamp> {
+        convert_iso_string_to_timestamp(iso_string)
+    }
+
+    #[test]
+    fn round_trip() {
+        let timestamp = parse("2024-05-20T07:26:40.123456789Z").unwrap();
+        assert_eq!(timestamp, Timestamp::from_nanos(1_716_190_000_123_456_789));
+        assert_eq!(convert_timestamp_to_iso_string(&timestamp), "2024-05-20T07:26:40.123456789Z");
+        assert_eq!(parse("1970-01-01T00:00:00Z").unwrap(), Timestamp::from_nanos(0));
+        assert_eq!(parse("2000-03-01T00:00:00.5Z").unwrap(), Timestamp::from_nanos(951_868_800_500_000_000));
+    }
+
+    #[test]
+    fn days_per_month() {
+        parse("2024-02-29T00:00:00Z").unwrap();
+        parse("2000-02-29T00:00:00Z").unwrap();
+        parse("2023-04-30T00:00:00Z").unwrap();
+        parse("2023-12-31T23:59:59Z").unwrap();
+        for invalid in [
+            "2024-02-31T00:00:00Z",
+            "2024-02-30T00:00:00Z",
+            "2023-02-29T00:00:00Z",
+            "2100-02-29T00:00:00Z",
+            "2023-04-31T00:00:00Z",
+            "2023-00-01T00:00:00Z",
+            "2023-13-01T00:00:00Z",
+            "2023-01-00T00:00:00Z",
+        ] {
+            assert!(parse(invalid).is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn out_of_range_is_an_error() {
+        // u64 nanoseconds end in july 2554
+        parse("2554-07-21T23:34:33Z").unwrap();
+        assert!(parse("2554-07-21T23:34:34Z").is_err());
+        assert!(parse("2600-01-01T00:00:00Z").is_err());
+        assert!(parse("18446744073709551615-01-01T00:00:00Z").is_err());
+        assert!(parse("1969-12-31T23:59:59Z").is_err());
+        assert!(parse("2024-01-01T24:00:00Z").is_err());
+        assert!(parse("2024-01-01").is_err());
+    }
+}
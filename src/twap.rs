@@ -0,0 +1,344 @@
+use cosmwasm_std::{Decimal256, Deps, Env, Order, SignedDecimal256, StdError, StdResult, Storage, Timestamp};
+use cw_storage_plus::Bound;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::currency_pair::CurrencyPair;
+use crate::error::ContractError;
+use crate::math::{exp, ln, ln_2, price_to_decimal};
+use crate::query::GetPriceResponse;
+use crate::state::Contract;
+
+/// observations older than this are pruned as new ones are recorded
+pub const OBSERVATION_RETENTION_SECONDS: u64 = 7 * 86_400;
+/// most observations read by a single ema query
+const MAX_OBSERVATIONS: usize = 500;
+const PRUNE_LIMIT: usize = 10;
+/// the ema ignores observations older than this many half-lives
+const EMA_HORIZON_HALF_LIVES: u64 = 16;
+
+/// A recorded price, keyed by pair and block time in seconds.
+///
+/// Carries the time weighted sums of the earlier observations, so a twap reads
+/// only the observations at both ends of its window.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct Observation {
+    pub timestamp: Timestamp,
+    /// in whole quote units
+    pub price: SignedDecimal256,
+    /// observations of the pair recorded before this one, pruned ones included
+    pub index: u64,
+    /// sum of price * seconds up to `timestamp`
+    pub cumulative: SignedDecimal256,
+    /// sum of ln(price) * seconds up to `timestamp`, non-positive prices accrue nothing
+    pub log_cumulative: SignedDecimal256,
+    /// non-positive prices up to this one, included
+    pub nonpositive: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, JsonSchema, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum TwapMode {
+    Arithmetic,
+    /// log-space mean, prices must be positive
+    Geometric,
+}
+
+impl Observation {
+    // sum of the mode at `time` in seconds, as if the price held until then
+    fn cumulative_at(&self, time: u64, mode: TwapMode) -> StdResult<SignedDecimal256> {
+        let elapsed = seconds(time.saturating_sub(self.timestamp.seconds()));
+        match mode {
+            TwapMode::Arithmetic => Ok(self.cumulative.checked_add(self.price.checked_mul(elapsed)?)?),
+            TwapMode::Geometric if self.price <= SignedDecimal256::zero() => Ok(self.log_cumulative),
+            TwapMode::Geometric => Ok(self.log_cumulative.checked_add(ln(positive(self.price)?)?.checked_mul(elapsed)?)?),
+        }
+    }
+}
+
+impl<'a> Contract<'a> {
+    pub(crate) fn observe(&self, storage: &mut dyn Storage, env: &Env, pair_id: &str, price: &GetPriceResponse) -> StdResult<()> {
+        let now = env.block.time.seconds();
+        let price = price_to_decimal(price.price.price, price.decimals)?;
+        let nonpositive = u64::from(price <= SignedDecimal256::zero());
+        // an observation of the same second is replaced, the sums continue from the one before
+        let previous = self.observations
+            .prefix(pair_id)
+            .range(storage, None, Some(Bound::exclusive(now)), Order::Descending)
+            .next()
+            .transpose()?;
+        let observation = match previous {
+            Some((_, previous)) => Observation {
+                timestamp: env.block.time,
+                price,
+                index: previous.index + 1,
+                cumulative: previous.cumulative_at(now, TwapMode::Arithmetic)?,
+                log_cumulative: previous.cumulative_at(now, TwapMode::Geometric)?,
+                nonpositive: previous.nonpositive + nonpositive,
+            },
+            None => Observation {
+                timestamp: env.block.time,
+                price,
+                index: 0,
+                cumulative: SignedDecimal256::zero(),
+                log_cumulative: SignedDecimal256::zero(),
+                nonpositive,
+            },
+        };
+        self.observations.save(storage, (pair_id, now), &observation)?;
+
+        // keep the latest observation before the cutoff, it is the price at the cutoff
+        let cutoff = now.saturating_sub(OBSERVATION_RETENTION_SECONDS);
+        let expired = self.observations
+            .prefix(pair_id)
+            .keys(storage, None, Some(Bound::exclusive(cutoff)), Order::Ascending)
+            .take(PRUNE_LIMIT + 1)
+            .collect::<StdResult<Vec<_>>>()?;
+        for seconds in expired.iter().take(expired.len().saturating_sub(1)) {
+            self.observations.remove(storage, (pair_id, *seconds));
+        }
+        Ok(())
+    }
+
+    pub(crate) fn get_twap(&self, deps: Deps, env: Env, pair: CurrencyPair, window: u64, mode: TwapMode) -> Result<TwapResponse, ContractError> {
+        let pair_id = pair.to_string();
+        self.assert_pairs_not_paused(deps.storage, std::slice::from_ref(&pair_id))?;
+        let now = env.block.time.seconds();
+        let start = now.saturating_sub(window);
+        let observations = self.observations.prefix(&pair_id);
+        let latest_until = |seconds: u64| {
+            observations
+                .range(deps.storage, None, Some(Bound::inclusive(seconds)), Order::Descending)
+                .next()
+                .transpose()
+        };
+        let last = latest_until(now)?.ok_or_else(|| StdError::not_found(format!("observations of {}", pair_id)))?.1;
+        // a history shorter than the window starts at the oldest observation
+        let first = match latest_until(start)? {
+            Some((_, first)) => first,
+            None => observations.range(deps.storage, None, None, Order::Ascending).next().transpose()?.map(|(_, first)| first).unwrap_or(last.clone()),
+        };
+
+        if mode == TwapMode::Geometric && last.nonpositive > first.nonpositive - u64::from(first.price <= SignedDecimal256::zero()) {
+            return Err(StdError::generic_err(format!("geometric mean of non-positive prices of {}", pair_id)).into());
+        }
+        let from = first.timestamp.seconds().max(start);
+        let covered = now - from;
+        let price = if covered == 0 {
+            last.price
+        } else {
+            let sum = last.cumulative_at(now, mode)?.checked_sub(first.cumulative_at(from, mode)?)?;
+            let mean = sum.checked_div(seconds(covered)).map_err(|err| StdError::generic_err(err.to_string()))?;
+            match mode {
+                TwapMode::Arithmetic => mean,
+                TwapMode::Geometric => SignedDecimal256::try_from(exp(mean)?)
+                    .map_err(|_| StdError::generic_err("twap overflows SignedDecimal256"))?,
+            }
+        };
+
+        Ok(TwapResponse {
+            pair_id,
+            mode,
+            price,
+            window,
+            covered_seconds: covered,
+            observations: (last.index - first.index + 1) as u32,
+        })
+    }
+
+    pub(crate) fn get_ema(&self, deps: Deps, env: Env, pair: CurrencyPair, half_life: u64) -> Result<EmaResponse, ContractError> {
+        if half_life == 0 {
+            return Err(StdError::generic_err("half_life must be positive").into());
+        }
+        let pair_id = pair.to_string();
+        self.assert_pairs_not_paused(deps.storage, std::slice::from_ref(&pair_id))?;
+        let now = env.block.time.seconds();
+        let start = now.saturating_sub(half_life.saturating_mul(EMA_HORIZON_HALF_LIVES));
+        let observations = self.observations_since(deps.storage, &pair_id, start, now)?;
+
+        // continuous ema of the recorded prices, each held until the next observation
+        let mut ema = observations[0].price;
+        for (i, observation) in observations.iter().enumerate() {
+            let from = observation.timestamp.seconds().max(start);
+            let until = observations.get(i + 1).map_or(now, |next| next.timestamp.seconds());
+            let decay = exp(-(ln_2().checked_mul(SignedDecimal256::from_ratio(until - from, half_life))?))?;
+            let decay = SignedDecimal256::try_from(decay).map_err(|_| StdError::generic_err("decay overflows SignedDecimal256"))?;
+            ema = observation.price.checked_add(ema.checked_sub(observation.price)?.checked_mul(decay)?)?;
+        }
+
+        Ok(EmaResponse {
+            pair_id,
+            half_life,
+            price: ema,
+            observations: observations.len() as u32,
+        })
+    }
+
+    // observations from the latest one at or before `start` up to `end`, oldest first,
+    // fails rather than leaving out the oldest ones beyond `MAX_OBSERVATIONS`
+    fn observations_since(&self, storage: &dyn Storage, pair_id: &str, start: u64, end: u64) -> Result<Vec<Observation>, ContractError> {
+        let mut observations = vec![];
+        for item in self.observations
+            .prefix(pair_id)
+            .range(storage, None, Some(Bound::inclusive(end)), Order::Descending)
+        {
+            if observations.len() == MAX_OBSERVATIONS {
+                return Err(ContractError::TooManyObservations { pair: pair_id.to_string(), max: MAX_OBSERVATIONS as u64 });
+            }
+            let (seconds, observation) = item?;
+            observations.push(observation);
+            if seconds <= start {
+                break;
+            }
+        }
+        if observations.is_empty() {
+            return Err(StdError::not_found(format!("observations of {}", pair_id)).into());
+        }
+        observations.reverse();
+        Ok(observations)
+    }
+}
+
+fn seconds(seconds: u64) -> SignedDecimal256 {
+    SignedDecimal256::from_ratio(seconds, 1u64)
+}
+
+fn positive(price: SignedDecimal256) -> StdResult<Decimal256> {
+    if price <= SignedDecimal256::zero() {
+        return Err(StdError::generic_err(format!("geometric mean of non-positive price {}", price)));
+    }
+    Decimal256::try_from(price).map_err(|err| StdError::generic_err(err.to_string()))
+}
+
+#[derive(Clone, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct TwapResponse {
+    pub pair_id: String,
+    pub mode: TwapMode,
+    pub price: SignedDecimal256,
+    /// requested window in seconds
+    pub window: u64,
+    /// part of the window covered by observations, less than `window` when history is shorter
+    pub covered_seconds: u64,
+    pub observations: u32,
+}
+
+#[derive(Clone, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct EmaResponse {
+    pub pair_id: String,
+    pub half_life: u64,
+    pub price: SignedDecimal256,
+    pub observations: u32,
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+    use crate::msgs::{ExecuteMsg, QueryMsg};
+    use crate::testing::{Suite, KEEPER};
+
+    fn decimal(value: &str) -> SignedDecimal256 {
+        SignedDecimal256::from_str(value).unwrap()
+    }
+
+    // records `price` and holds it for `seconds`
+    fn record(suite: &mut Suite, price: i128, seconds: u64) {
+        suite.set_price("BTC/USD", price, 0);
+        suite.execute(KEEPER, &ExecuteMsg::QuoteAndRecord { pairs: vec!["BTC/USD".parse().unwrap()] }, &[]).unwrap();
+        suite.advance(seconds);
+    }
+
+    fn twap(suite: &Suite, window: u64, mode: TwapMode) -> StdResult<TwapResponse> {
+        suite.query(&QueryMsg::GetTwap { pair: "BTC/USD".parse().unwrap(), window, mode })
+    }
+
+    fn ema(suite: &Suite, half_life: u64) -> StdResult<EmaResponse> {
+        suite.query(&QueryMsg::GetEma { pair: "BTC/USD".parse().unwrap(), half_life })
+    }
+
+    fn assert_close(value: SignedDecimal256, expected: &str) {
+        let tolerance = decimal("0.000001");
+        assert!(value > decimal(expected) - tolerance && value < decimal(expected) + tolerance, "{} is not {}", value, expected);
+    }
+
+    #[test]
+    fn arithmetic_twap_weights_prices_by_duration() {
+        let mut suite = Suite::new();
+        assert!(twap(&suite, 60, TwapMode::Arithmetic).is_err());
+        record(&mut suite, 100, 20);
+        record(&mut suite, 200, 20);
+        record(&mut suite, 450, 20);
+
+        let res = twap(&suite, 60, TwapMode::Arithmetic).unwrap();
+        assert_eq!((res.price, res.covered_seconds, res.observations), (decimal("250"), 60, 3));
+        // the window starts within the first price
+        let res = twap(&suite, 50, TwapMode::Arithmetic).unwrap();
+        assert_eq!((res.price, res.covered_seconds, res.observations), (decimal("280"), 50, 3));
+        let res = twap(&suite, 20, TwapMode::Arithmetic).unwrap();
+        assert_eq!((res.price, res.observations), (decimal("450"), 1));
+        // a history shorter than the window
+        let res = twap(&suite, 600, TwapMode::Arithmetic).unwrap();
+        assert_eq!((res.price, res.covered_seconds), (decimal("250"), 60));
+        assert_eq!(twap(&suite, 0, TwapMode::Arithmetic).unwrap().price, decimal("450"));
+    }
+
+    #[test]
+    fn replaced_observations_do_not_double_count() {
+        let mut suite = Suite::new();
+        record(&mut suite, 100, 10);
+        record(&mut suite, 300, 0);
+        record(&mut suite, 200, 10);
+
+        let res = twap(&suite, 20, TwapMode::Arithmetic).unwrap();
+        assert_eq!((res.price, res.observations), (decimal("150"), 2));
+    }
+
+    #[test]
+    fn twap_reads_beyond_the_ema_observation_limit() {
+        let mut suite = Suite::new();
+        for i in 0..=MAX_OBSERVATIONS as i128 {
+            record(&mut suite, 100 + i % 2, 1);
+        }
+        let res = twap(&suite, 1_000, TwapMode::Arithmetic).unwrap();
+        assert_eq!((res.covered_seconds, res.observations), (MAX_OBSERVATIONS as u64 + 1, MAX_OBSERVATIONS as u32 + 1));
+        assert_close(res.price, &format!("{}", 100.0 + 250.0 / 501.0));
+
+        let err = ema(&suite, 60).unwrap_err().to_string();
+        assert!(err.contains("More than 500 observations of BTC/USD in the window"), "{}", err);
+        ema(&suite, 10).unwrap();
+    }
+
+    #[test]
+    fn geometric_twap_requires_positive_prices() {
+        let mut suite = Suite::new();
+        record(&mut suite, 100, 10);
+        record(&mut suite, 400, 10);
+        let res = twap(&suite, 20, TwapMode::Geometric).unwrap();
+        assert_close(res.price, "200");
+
+        record(&mut suite, 0, 10);
+        record(&mut suite, 900, 10);
+        let err = twap(&suite, 15, TwapMode::Geometric).unwrap_err().to_string();
+        assert!(err.contains("geometric mean of non-positive prices of BTC/USD"), "{}", err);
+        // windows past the non-positive price
+        assert_close(twap(&suite, 10, TwapMode::Geometric).unwrap().price, "900");
+        // the window starting at the zero price
+        assert!(twap(&suite, 20, TwapMode::Geometric).is_err());
+        twap(&suite, 20, TwapMode::Arithmetic).unwrap();
+    }
+
+    #[test]
+    fn ema_weights_recent_prices() {
+        let mut suite = Suite::new();
+        assert!(ema(&suite, 0).unwrap_err().to_string().contains("half_life must be positive"));
+        record(&mut suite, 100, 60);
+        assert_eq!(ema(&suite, 60).unwrap().price, decimal("100"));
+
+        // one half-life after the move the ema is half way
+        record(&mut suite, 200, 60);
+        let res = ema(&suite, 60).unwrap();
+        assert_close(res.price, "150");
+        assert_eq!(res.observations, 2);
+    }
+}